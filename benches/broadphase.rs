@@ -0,0 +1,100 @@
+//! Times simulation steps on large generated levels, and compares the
+//! spatial grid broadphase with the pairwise check it replaced, where every
+//! moving body was tested against every other body.
+//!
+//! Run with `cargo bench --no-default-features --bench broadphase`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rust_2d_engine::components::{Position, Renderable, Velocity};
+use rust_2d_engine::spatial::SpatialGrid;
+use rust_2d_engine::geometry::{check_aabb, Rect};
+use rust_2d_engine::GameState;
+use specs::{Entity, Join, WorldExt};
+
+const STEPS: u32 = 120;
+
+/// A level `width` tiles wide with a floor every fourth row, an enemy on
+/// every floor and the player on the top one.
+fn generate_level(width: usize, floors: usize) -> String {
+    let mut level = String::new();
+    for floor in 0..floors {
+        for row in 0..3 {
+            let mut line = vec![' '; width];
+            if row == 2 {
+                line[width / 2] = 'E';
+                if floor == 0 {
+                    line[2] = 'P';
+                }
+            }
+            level.extend(line);
+            level.push('\n');
+        }
+        level.push_str(&"#".repeat(width));
+        level.push('\n');
+    }
+    level
+}
+
+/// Milliseconds per call of `run`, averaged over `STEPS` calls.
+fn time_ms<F: FnMut()>(mut run: F) -> f64 {
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        run();
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / f64::from(STEPS)
+}
+
+/// Counts the overlapping pairs of a moving body and any other body by
+/// testing every pair.
+fn pairwise(movers: &[(Entity, Rect)], bodies: &[(Entity, Rect)]) -> usize {
+    movers.iter()
+        .map(|(mover, rect)| bodies.iter().filter(|(other, other_rect)| other != mover && check_aabb(*rect, *other_rect)).count())
+        .sum()
+}
+
+/// The same count, testing only the bodies the grid returns.
+fn through_grid(movers: &[(Entity, Rect)], grid: &SpatialGrid) -> usize {
+    movers.iter()
+        .map(|(mover, rect)| {
+            grid.query_aabb(*rect).into_iter()
+                .filter(|other| other != mover && grid.rect(*other).is_some_and(|other_rect| check_aabb(*rect, other_rect)))
+                .count()
+        })
+        .sum()
+}
+
+fn main() {
+    for &(width, floors) in &[(100, 10), (500, 20), (1000, 50)] {
+        let mut state = GameState::new();
+        state.load_level(&generate_level(width, floors)).expect("generated level is valid");
+        let step = time_ms(|| state.step());
+
+        let (movers, bodies) = {
+            let entities = state.world.entities();
+            let positions = state.world.read_storage::<Position>();
+            let renderables = state.world.read_storage::<Renderable>();
+            let velocities = state.world.read_storage::<Velocity>();
+            let mut movers = Vec::new();
+            let mut bodies = Vec::new();
+            for (entity, pos, render) in (&entities, &positions, &renderables).join() {
+                let rect = (pos.x, pos.y, render.width, render.height);
+                bodies.push((entity, rect));
+                if velocities.contains(entity) {
+                    movers.push((entity, rect));
+                }
+            }
+            (movers, bodies)
+        };
+        let grid = state.world.read_resource::<SpatialGrid>();
+        assert_eq!(pairwise(&movers, &bodies), through_grid(&movers, &grid), "both broadphases find the same pairs");
+        let naive = time_ms(|| { black_box(pairwise(black_box(&movers), black_box(&bodies))); });
+        let indexed = time_ms(|| { black_box(through_grid(black_box(&movers), black_box(&grid))); });
+
+        println!(
+            "{:>6} tiles, {:>3} enemies: {:.3} ms/step; broadphase {:.4} ms pairwise, {:.4} ms with the grid ({:.0}x)",
+            width * floors, floors, step, naive, indexed, naive / indexed,
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use rust_2d_engine::{GameMode, GameState, HeadlessRunner, InputScript};
+use rust_2d_engine::utils::InputAction;
+
+const LEVEL: &str = "
+####################
+#                  #
+#                  #
+#    P      C     G#
+#   ###############
+####################";
+
+fn main() {
+    let mut state = GameState::builder()
+        .with_mode(GameMode::Playing)
+        .build();
+    state.load_level(LEVEL).expect("built-in level is valid");
+
+    // Walk right and jump once on the way to the goal
+    let script = InputScript::new()
+        .hold(InputAction::MoveRight, 0, 600)
+        .press(30, InputAction::Jump)
+        .release(31, InputAction::Jump);
+
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    while runner.frame() < 600 {
+        runner.step();
+        if runner.state.player_reached_goal() {
+            println!("Reached the goal after {} frames", runner.frame());
+            return;
+        }
+    }
+    println!("Did not reach the goal in {} frames", runner.frame());
+}
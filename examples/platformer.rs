@@ -0,0 +1,135 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rust_2d_engine::{GameMode, GameState};
+use rust_2d_engine::utils::{handle_input, render_game, Gamepads, InputMap};
+
+const LEVELS_DIR: &str = "assets/levels";
+const FIRST_LEVEL: &str = "tutorial";
+
+fn level_path(name: &str) -> PathBuf {
+    Path::new(LEVELS_DIR).join(format!("{}.level", name))
+}
+
+/// Moves between the non-playing screens on any key or button press.
+fn advance_menu(game_state: &mut GameState, record: bool) -> Result<(), String> {
+    match game_state.mode {
+        GameMode::Menu => {
+            game_state.mode = GameMode::Tutorial;
+            game_state.load_level_file(level_path(FIRST_LEVEL))
+                .map_err(|e| format!("Level Error ({}): {}", FIRST_LEVEL, e))?;
+            if record {
+                game_state.start_recording();
+            }
+        },
+        GameMode::GameOver | GameMode::Win => {
+            game_state.mode = GameMode::Menu;
+            game_state.clear();
+        },
+        GameMode::Tutorial | GameMode::Playing => {},
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), String> {
+    // `--record <path>` saves a replay of the session, starting at the tutorial
+    let args: Vec<String> = std::env::args().collect();
+    let record_path = args.iter()
+        .position(|arg| arg == "--record")
+        .and_then(|i| args.get(i + 1).cloned());
+
+    let sdl_context = sdl2::init().map_err(|e| format!("Init Error: {}", e))?;
+    let video_subsystem = sdl_context.video().map_err(|e| format!("Video Error: {}", e))?;
+
+    let window = video_subsystem.window("Rust 2D Platformer", 800, 600)
+        .position_centered()
+        .opengl()
+        .build()
+        .map_err(|e| format!("Window Error: {}", e))?;
+
+    let mut canvas = window.into_canvas()
+        .accelerated()
+        .present_vsync()
+        .build()
+        .map_err(|e| format!("Canvas Error: {}", e))?;
+
+    let controller_subsystem = sdl_context.game_controller().map_err(|e| format!("Controller Error: {}", e))?;
+    let mut gamepads = Gamepads::new(controller_subsystem);
+
+    let mut event_pump = sdl_context.event_pump().map_err(|e| format!("Event Pump Error: {}", e))?;
+    let input_map = InputMap::load("assets/controls.cfg").unwrap_or_else(|e| {
+        println!("Using default controls ({})", e);
+        InputMap::default()
+    });
+    let mut game_state = GameState::builder()
+        .with_input_map(input_map)
+        .build();
+    if let Err(e) = game_state.load_prefabs("assets/prefabs.ron") {
+        println!("Using built-in prefabs only ({})", e);
+    }
+    let mut last_update = Instant::now();
+
+    let mut running = true;
+    while running {
+        let now = Instant::now();
+        let frame_time = now.duration_since(last_update).as_secs_f32();
+        last_update = now;
+
+        for event in event_pump.poll_iter() {
+            let in_level = game_state.mode == GameMode::Tutorial || game_state.mode == GameMode::Playing;
+            match event {
+                Event::Quit {..} |
+                Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+                    running = false;
+                },
+                // Held keys stay pressed in the ActionState, so skip auto-repeat
+                Event::KeyDown { repeat: true, .. } => {},
+                Event::KeyDown { .. } | Event::ControllerButtonDown { .. } if !in_level => {
+                    advance_menu(&mut game_state, record_path.is_some())?;
+                },
+                Event::KeyDown { keycode: Some(keycode), .. } => {
+                    handle_input(&mut game_state, keycode, true);
+                },
+                // Always forward releases, so a key let go outside a level
+                // cannot stay held
+                Event::KeyUp { keycode: Some(keycode), .. } => {
+                    handle_input(&mut game_state, keycode, false);
+                },
+                _ => {
+                    gamepads.handle_event(&event, &mut game_state);
+                }
+            }
+        }
+
+        if game_state.mode == GameMode::Tutorial || game_state.mode == GameMode::Playing {
+            game_state.update(frame_time);
+
+            // Check Lose Condition (Fall out of the level)
+            if game_state.mode == GameMode::Playing && game_state.player_below(game_state.world_bounds().max_y) {
+                game_state.mode = GameMode::GameOver;
+            } else if game_state.player_reached_goal() {
+                // Reaching the goal moves on to the level's `next`, or wins the game
+                match game_state.level_info().next {
+                    Some(next) => {
+                        game_state.mode = GameMode::Playing;
+                        game_state.load_level_file(level_path(&next))
+                            .map_err(|e| format!("Level Error ({}): {}", next, e))?;
+                    },
+                    None => game_state.mode = GameMode::Win,
+                }
+            }
+        }
+
+        render_game(&game_state.world, &mut canvas, game_state.mode, game_state.alpha())?;
+        canvas.present();
+    }
+
+    if let (Some(path), Some(replay)) = (record_path, game_state.stop_recording()) {
+        replay.save(&path).map_err(|e| format!("Replay Error: {}", e))?;
+        println!("Saved replay to {}", path);
+    }
+
+    Ok(())
+}
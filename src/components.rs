@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use specs_derive::Component;
 
@@ -17,11 +18,232 @@ pub struct Player {
     pub score: i32,
 }
 
-#[derive(Component, Debug)]
+/// Feel of a `CharacterController`. Accelerations are in px/s², speeds in
+/// px/s and windows in seconds. Missing fields in prefab files take the
+/// default.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControllerTuning {
+    /// How fast the body reaches its walking speed, or stops, on the ground.
+    pub ground_acceleration: f32,
+    /// The same in the air.
+    pub air_acceleration: f32,
+    pub max_fall_speed: f32,
+    /// How long after walking off a ledge a jump is still allowed.
+    pub coyote_time: f32,
+    /// How long a jump pressed just before landing is remembered.
+    pub jump_buffer: f32,
+    /// Share of the upward speed kept when jump is released mid-rise.
+    pub jump_cut: f32,
+}
+
+impl Default for ControllerTuning {
+    fn default() -> Self {
+        ControllerTuning {
+            ground_acceleration: 6000.0,
+            air_acceleration: 1500.0,
+            max_fall_speed: 1000.0,
+            coyote_time: 0.1,
+            jump_buffer: 0.1,
+            jump_cut: 0.5,
+        }
+    }
+}
+
+/// Platformer movement for a `Player`: walking, jumping and falling as set
+/// by its `tuning`. Whatever drives the body (normally `PlayerControlSystem`)
+/// fills in the intent fields each step, and `CharacterControllerSystem`
+/// turns them into velocity.
+#[derive(Component, Debug, Clone, Default)]
+#[storage(VecStorage)]
+pub struct CharacterController {
+    pub tuning: ControllerTuning,
+    /// Wanted horizontal movement, from -1 (full left) to 1 (full right).
+    pub move_x: f32,
+    /// Jump was pressed this step.
+    pub jump_pressed: bool,
+    /// Jump is being held down.
+    pub jump_held: bool,
+    /// Coyote time left since the body was last grounded.
+    pub coyote: f32,
+    /// Time left on a buffered jump press.
+    pub buffered: f32,
+    /// Rising from a jump that can still be cut short.
+    pub rising: bool,
+}
+
+impl CharacterController {
+    pub fn new(tuning: ControllerTuning) -> Self {
+        CharacterController { tuning, ..Default::default() }
+    }
+}
+
+/// Shape of a `Collider`, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ColliderShape {
+    Aabb { width: f32, height: f32 },
+    Circle { radius: f32 },
+}
+
+/// Set of collision layers, as a bitmask. The first six bits are the
+/// built-in layers; games may use `layer6` to `layer31` for their own. In
+/// prefab files a set is written as a list of names, e.g.
+/// `["player", "enemy"]`, or `["all"]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct Layers(pub u32);
+
+impl Layers {
+    pub const NONE: Layers = Layers(0);
+    pub const PLAYER: Layers = Layers(1 << 0);
+    pub const ENEMY: Layers = Layers(1 << 1);
+    pub const PLATFORM: Layers = Layers(1 << 2);
+    pub const COLLECTIBLE: Layers = Layers(1 << 3);
+    pub const GOAL: Layers = Layers(1 << 4);
+    pub const PARTICLE: Layers = Layers(1 << 5);
+    pub const ALL: Layers = Layers(u32::MAX);
+
+    const NAMES: [&'static str; 6] = ["player", "enemy", "platform", "collectible", "goal", "particle"];
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether the two sets share at least one layer.
+    pub fn intersects(self, other: Layers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn contains(self, other: Layers) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Layers {
+    type Output = Layers;
+
+    fn bitor(self, other: Layers) -> Layers {
+        Layers(self.0 | other.0)
+    }
+}
+
+impl TryFrom<Vec<String>> for Layers {
+    type Error = String;
+
+    fn try_from(names: Vec<String>) -> Result<Self, String> {
+        names.iter().try_fold(Layers::NONE, |layers, name| {
+            let bit = match Layers::NAMES.iter().position(|known| known == name) {
+                Some(bit) => bit as u32,
+                None if name == "all" => return Ok(Layers::ALL),
+                None => name.strip_prefix("layer")
+                    .and_then(|bit| bit.parse().ok())
+                    .filter(|bit| *bit < 32)
+                    .ok_or_else(|| format!("unknown collision layer '{}'", name))?,
+            };
+            Ok(layers | Layers(1 << bit))
+        })
+    }
+}
+
+impl From<Layers> for Vec<String> {
+    fn from(layers: Layers) -> Self {
+        if layers == Layers::ALL {
+            return vec!["all".to_string()];
+        }
+        (0..32)
+            .filter(|bit| layers.0 & (1 << bit) != 0)
+            .map(|bit| Layers::NAMES.get(bit).map_or_else(|| format!("layer{}", bit), |name| name.to_string()))
+            .collect()
+    }
+}
+
+/// What a body is and what it interacts with. A moving body is stopped by
+/// anything on a layer in `blocks`, and reports contacts with anything on a
+/// layer in `triggers`. Layers in neither are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub layers: Layers,
+    pub blocks: Layers,
+    pub triggers: Layers,
+}
+
+impl CollisionFilter {
+    /// Defaults for an entity on `kind`, the layers of its marker
+    /// components: players and enemies are blocked by platforms and report
+    /// contacts with everything; other bodies only get hit.
+    pub fn for_kind(kind: Layers) -> Self {
+        let moving = kind.intersects(Layers::PLAYER | Layers::ENEMY);
+        CollisionFilter {
+            layers: kind,
+            blocks: if moving { Layers::PLATFORM } else { Layers::NONE },
+            triggers: if moving { Layers::ALL } else { Layers::NONE },
+        }
+    }
+
+    /// The kind's defaults with any layers set on `collider` taking their
+    /// place.
+    pub fn resolve(collider: Option<&Collider>, kind: Layers) -> Self {
+        let defaults = CollisionFilter::for_kind(kind);
+        match collider {
+            Some(collider) => CollisionFilter {
+                layers: collider.layers.unwrap_or(defaults.layers),
+                blocks: collider.blocks.unwrap_or(defaults.blocks),
+                triggers: collider.triggers.unwrap_or(defaults.triggers),
+            },
+            None => defaults,
+        }
+    }
+}
+
+/// Collision shape, independent of the visual size. `offset` places the
+/// shape's bounding box relative to the entity's `Position`, so a circle of
+/// radius `r` with no offset is centred at `(x + r, y + r)`. Entities
+/// without a collider collide as their `Renderable` rectangle.
+///
+/// Layers left as `None` come from the entity's kind, see
+/// `CollisionFilter::for_kind`.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
 #[storage(VecStorage)]
-pub struct Collidable {
-    #[allow(dead_code)]
-    pub radius: f32,
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub offset: (f32, f32),
+    pub layers: Option<Layers>,
+    pub blocks: Option<Layers>,
+    pub triggers: Option<Layers>,
+}
+
+impl Collider {
+    pub fn aabb(width: f32, height: f32) -> Self {
+        Collider::new(ColliderShape::Aabb { width, height })
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Collider::new(ColliderShape::Circle { radius })
+    }
+
+    fn new(shape: ColliderShape) -> Self {
+        Collider { shape, offset: (0.0, 0.0), layers: None, blocks: None, triggers: None }
+    }
+
+    pub fn with_offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = (x, y);
+        self
+    }
+
+    pub fn with_layers(mut self, layers: Layers) -> Self {
+        self.layers = Some(layers);
+        self
+    }
+
+    pub fn with_blocks(mut self, blocks: Layers) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+
+    pub fn with_triggers(mut self, triggers: Layers) -> Self {
+        self.triggers = Some(triggers);
+        self
+    }
 }
 
 #[derive(Component, Debug)]
@@ -32,13 +254,18 @@ pub struct ParticleEmitter {
     pub color: (u8, u8, u8),
 }
 
-#[derive(Component, Debug)]
-#[storage(VecStorage)]
+#[derive(Debug)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
 }
 
+/// Stored with change events, so `SpatialIndexSystem` only re-indexes
+/// bodies that were added, moved or removed.
+impl Component for Position {
+    type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+}
+
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
 pub struct Velocity {
@@ -56,9 +283,19 @@ pub struct Lifetime {
 #[storage(VecStorage)]
 pub struct Collectible;
 
-#[derive(Component, Debug, Default)]
-#[storage(NullStorage)]
-pub struct Gravity;
+/// Falls under the level's gravity, multiplied by `scale`. Negative scales
+/// make a body float upwards.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[storage(VecStorage)]
+pub struct Gravity {
+    pub scale: f32,
+}
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity { scale: 1.0 }
+    }
+}
 
 #[derive(Component, Debug, Default)]
 #[storage(NullStorage)]
@@ -74,7 +311,6 @@ pub struct Enemy;
 
 #[derive(Component, Debug)]
 #[storage(VecStorage)]
-#[allow(dead_code)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
@@ -93,3 +329,189 @@ pub struct Animation {
 #[derive(Component, Debug, Default)]
 #[storage(NullStorage)]
 pub struct Goal;
+
+/// Position at the start of the current simulation step, used to
+/// interpolate rendering between fixed steps.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct PreviousPosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Draw layer; higher layers are drawn on top. Entities without one draw on
+/// layer 0.
+#[derive(Component, Debug, Clone, Copy, Default)]
+#[storage(VecStorage)]
+pub struct DrawOrder {
+    pub layer: i32,
+}
+
+/// Health removed from a player that touches this entity. Enemies without
+/// one deal `CombatSettings::contact_damage`.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct Damage {
+    pub amount: i32,
+}
+
+/// Ignores further damage until `remaining` seconds have passed.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct Invulnerable {
+    pub remaining: f32,
+}
+
+/// Being knocked back from a hit; player input is ignored until `remaining`
+/// seconds have passed.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct Knockback {
+    pub remaining: f32,
+}
+
+/// Platform that only blocks bodies landing on it from above.
+#[derive(Component, Debug, Default)]
+#[storage(NullStorage)]
+pub struct OneWay;
+
+/// Falling through `OneWay` platforms until `remaining` seconds have passed.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct DropThrough {
+    pub remaining: f32,
+}
+
+/// Platform whose top is an incline instead of flat. `left` and `right` are
+/// the surface's height above the tile's bottom edge at each side, as a
+/// fraction of the tile's height: `Slope { left: 0.0, right: 1.0 }` is a
+/// 45° ramp up to the right on a square tile. Slopes are only solid from
+/// above.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[storage(VecStorage)]
+pub struct Slope {
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Slope {
+    /// Height of the surface of `rect` at `x`, as a y coordinate.
+    pub fn surface_y(&self, rect: (f32, f32, f32, f32), x: f32) -> f32 {
+        let along = ((x - rect.0) / rect.2).clamp(0.0, 1.0);
+        rect.1 + rect.3 * (1.0 - (self.left + (self.right - self.left) * along))
+    }
+
+    /// Rise of the surface per pixel to the right on a tile of `rect`.
+    pub fn gradient(&self, rect: (f32, f32, f32, f32)) -> f32 {
+        (self.right - self.left) * rect.3 / rect.2
+    }
+}
+
+/// Standing on a `Slope` whose surface rises by `gradient` per pixel to the
+/// right. Set by `CollisionSystem` alongside `Grounded`.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct OnSlope {
+    pub gradient: f32,
+}
+
+/// Surface properties of a platform, changing how bodies move on it: ice
+/// has low `friction`, mud a low `speed`, bouncy pads some `bounciness`.
+/// Missing fields in prefab files take the default of a plain platform.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[storage(VecStorage)]
+#[serde(default)]
+pub struct PhysicsMaterial {
+    /// Grip, scaling how quickly bodies standing on it speed up and slow
+    /// down.
+    pub friction: f32,
+    /// Share of the speed into the surface a body bounces back with.
+    pub bounciness: f32,
+    /// Multiplier on the walking speed of players standing on it.
+    pub speed: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial { friction: 1.0, bounciness: 0.0, speed: 1.0 }
+    }
+}
+
+/// Standing on a platform with a `PhysicsMaterial`. Set by
+/// `CollisionSystem` alongside `Grounded`.
+#[derive(Component, Debug, Clone, Copy)]
+#[storage(VecStorage)]
+pub struct OnMaterial {
+    pub material: PhysicsMaterial,
+}
+
+/// How a `MovingPlatform` goes round its waypoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PathMode {
+    /// In order, then straight back to the first and round again.
+    Linear,
+    /// In order, then back through them in reverse.
+    #[default]
+    PingPong,
+}
+
+/// Kinematic platform following a path of waypoints, carrying anything
+/// standing on it. Waypoints are relative to where the platform spawned,
+/// which is the first waypoint. It moves by setting its `Velocity`.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[storage(VecStorage)]
+pub struct MovingPlatform {
+    pub path: Vec<(f32, f32)>,
+    /// Pixels per second.
+    pub speed: f32,
+    /// Seconds to pause at each waypoint.
+    pub wait: f32,
+    pub mode: PathMode,
+    /// Spawn position, set on the first step.
+    pub origin: Option<(f32, f32)>,
+    /// Index of the waypoint being headed for; 0 is the spawn position.
+    pub target: usize,
+    /// Whether a ping-pong path is currently being walked backwards.
+    pub reversing: bool,
+    pub waiting: f32,
+}
+
+impl MovingPlatform {
+    pub fn new(path: Vec<(f32, f32)>, speed: f32, wait: f32, mode: PathMode) -> Self {
+        MovingPlatform { path, speed, wait, mode, origin: None, target: 1, reversing: false, waiting: 0.0 }
+    }
+
+    /// Waypoint `index` relative to the spawn position, or `None` past the
+    /// end of the path.
+    pub fn waypoint(&self, index: usize) -> Option<(f32, f32)> {
+        match index {
+            0 => Some((0.0, 0.0)),
+            _ => self.path.get(index - 1).copied(),
+        }
+    }
+
+    /// Moves `target` on to the next waypoint. Without a path the only
+    /// waypoint is the spawn position.
+    pub fn advance(&mut self) {
+        if self.path.is_empty() {
+            self.target = 0;
+            return;
+        }
+        let count = self.path.len() + 1;
+        self.target = match self.mode {
+            PathMode::Linear => (self.target + 1) % count,
+            PathMode::PingPong => {
+                if self.target == 0 || self.target + 1 == count {
+                    self.reversing = self.target != 0;
+                }
+                if self.reversing { self.target - 1 } else { self.target + 1 }
+            },
+        };
+    }
+}
+
+/// Health reached zero. Dead players no longer respond to input or take
+/// damage.
+#[derive(Component, Debug, Default)]
+#[storage(NullStorage)]
+pub struct Dead;
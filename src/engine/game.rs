@@ -0,0 +1,500 @@
+use specs::{World, WorldExt, Dispatcher, DispatcherBuilder, System, RunNow, Join};
+use std::path::Path;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::engine::FixedTimestep;
+use crate::engine::replay::{InputRecorder, Replay};
+use crate::prefab::{PrefabError, PrefabRegistry};
+use crate::resources::{Camera, CombatSettings, GameRng, PhysicsConfig, WorldBounds};
+use crate::spatial::SpatialGrid;
+
+use crate::components::{Player, CharacterController, Renderable, Position, Velocity, Collider, ParticleEmitter, Collectible, Lifetime, Gravity, Grounded, Platform, Enemy, Health, Goal, PreviousPosition, DrawOrder, Damage, Invulnerable, Knockback, Dead, OneWay, DropThrough, MovingPlatform, Slope, OnSlope, PhysicsMaterial, OnMaterial};
+use crate::systems::{PlayerControlSystem, CameraSystem, MovementSystem, CollisionSystem, ParticleSystem, LogicSystem, EnemyAISystem, HealthSystem, ScoreSystem, DamageSystem, SpatialIndexSystem, MovingPlatformSystem, CharacterControllerSystem, body_shape};
+use crate::geometry::shapes_overlap;
+use crate::utils::input::{ActionState, HeldBindings, InputAction, InputAxis, InputEvent, InputMap};
+use crate::utils::level_loader::{load_level, load_level_file, LevelError, LevelInfo};
+use crate::utils::tiled::{load_tiled_file, TiledError};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameMode {
+    Menu,
+    Tutorial,
+    Playing,
+    GameOver,
+    Win,
+}
+
+pub struct GameState {
+    pub world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    timestep: FixedTimestep,
+    tick: u64,
+    paused: bool,
+    pending: Vec<InputEvent>,
+    held: HeldBindings,
+    recorder: Option<InputRecorder>,
+    pub mode: GameMode,
+}
+
+impl GameState {
+    /// Creates a game state with the default systems and an empty world.
+    pub fn new() -> Self {
+        GameStateBuilder::new().build()
+    }
+
+    pub fn builder() -> GameStateBuilder {
+        GameStateBuilder::new()
+    }
+
+    /// Feeds a frame's elapsed time into the fixed-step accumulator and runs
+    /// as many simulation steps as are due. Returns the number of steps run.
+    pub fn update(&mut self, frame_time: f32) -> u32 {
+        let steps = self.timestep.advance(frame_time);
+        for _ in 0..steps {
+            self.step();
+        }
+        steps
+    }
+
+    /// Runs exactly one fixed simulation step. While paused, input is still
+    /// processed but the systems do not run. A player dying during a level
+    /// switches the mode to `GameOver`.
+    pub fn step(&mut self) {
+        self.apply_pending_input();
+        if self.world.read_resource::<ActionState>().just_pressed(InputAction::Pause) {
+            self.paused = !self.paused;
+        }
+
+        if !self.paused {
+            self.snapshot_positions();
+            self.world.insert(self.timestep.step());
+            self.dispatcher.dispatch(&self.world);
+            self.world.maintain();
+
+            let in_level = self.mode == GameMode::Tutorial || self.mode == GameMode::Playing;
+            if in_level && self.player_dead() {
+                self.mode = GameMode::GameOver;
+            }
+        }
+
+        self.world.write_resource::<ActionState>().clear_transitions();
+        self.tick += 1;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Number of simulation steps run so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Looks up the action bound to a physical key in the `InputMap` resource.
+    pub fn action_for_key(&self, key: &str) -> Option<InputAction> {
+        self.world.read_resource::<InputMap>().action_for(key)
+    }
+
+    /// Queues the input for a physical key going down or up, looked up in the
+    /// `InputMap`. An action bound to several keys stays pressed until all of
+    /// them are up, and repeated presses of a held key are ignored.
+    pub fn key_input(&mut self, key: &str, pressed: bool) {
+        self.binding_input(key.to_string(), key, pressed);
+    }
+
+    /// Like `key_input`, for a gamepad button (`Pad:a`, ...) on the
+    /// controller with instance id `pad`. The same button on two controllers
+    /// is held independently.
+    pub fn pad_input(&mut self, pad: u32, button: &str, pressed: bool) {
+        self.binding_input(format!("{}#{}", button, pad), button, pressed);
+    }
+
+    fn binding_input(&mut self, binding: String, key: &str, pressed: bool) {
+        if pressed {
+            if let Some(action) = self.action_for_key(key) {
+                if self.held.press(&binding, action) {
+                    self.input(action, true);
+                }
+            }
+        } else if let Some(action) = self.held.release(&binding) {
+            self.input(action, false);
+        }
+    }
+
+    /// Queues a logical input change. It is applied at the start of the next
+    /// step, so input lines up with simulation ticks and can be recorded.
+    pub fn input(&mut self, action: InputAction, pressed: bool) {
+        self.input_event(InputEvent::Action(action, pressed));
+    }
+
+    /// Queues a new analog axis value, already deadzone-adjusted.
+    pub fn input_axis(&mut self, axis: InputAxis, value: f32) {
+        self.input_event(InputEvent::Axis(axis, value));
+    }
+
+    pub fn input_event(&mut self, event: InputEvent) {
+        self.pending.push(event);
+    }
+
+    /// Starts recording input into a `Replay`. Call this right after loading
+    /// a level: the RNG is reseeded so that replaying the inputs on a freshly
+    /// built state with the same seed and level reproduces the session.
+    /// Actions already held are recorded as pressed on the first frame.
+    pub fn start_recording(&mut self) {
+        let seed = self.world.read_resource::<GameRng>().seed();
+        self.world.insert(GameRng::seeded(seed));
+        let rate = 1.0 / self.timestep.step();
+        let level = Some(self.level_info().name).filter(|name| !name.is_empty());
+        let mut recorder = InputRecorder::new(seed, rate, level, self.tick);
+        for event in self.world.read_resource::<ActionState>().held_events() {
+            recorder.record(self.tick, event);
+        }
+        self.recorder = Some(recorder);
+    }
+
+    /// Stops recording and returns the captured session, if any.
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        self.recorder.take().map(|recorder| recorder.finish(self.tick))
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn timestep(&self) -> &FixedTimestep {
+        &self.timestep
+    }
+
+    /// Interpolation factor between the previous and current step, to be
+    /// passed to the renderer. Pinned to 1.0 while paused, so the frozen
+    /// scene is drawn where the simulation stopped.
+    pub fn alpha(&self) -> f32 {
+        if self.paused {
+            1.0
+        } else {
+            self.timestep.alpha()
+        }
+    }
+
+    /// Hashes the bit patterns of every `Position` and `Velocity` in entity
+    /// order. Two runs with the same seed, level and input stream produce the
+    /// same hash after the same number of steps.
+    pub fn state_hash(&self) -> u64 {
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let velocities = self.world.read_storage::<Velocity>();
+        let mut hasher = DefaultHasher::new();
+
+        for (entity, pos) in (&entities, &positions).join() {
+            entity.id().hash(&mut hasher);
+            pos.x.to_bits().hash(&mut hasher);
+            pos.y.to_bits().hash(&mut hasher);
+            if let Some(vel) = velocities.get(entity) {
+                vel.x.to_bits().hash(&mut hasher);
+                vel.y.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    fn apply_pending_input(&mut self) {
+        let mut actions = self.world.write_resource::<ActionState>();
+        for event in self.pending.drain(..) {
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(self.tick, event);
+            }
+            actions.apply(event);
+        }
+    }
+
+    fn snapshot_positions(&mut self) {
+        let entities = self.world.entities();
+        let positions = self.world.read_storage::<Position>();
+        let velocities = self.world.read_storage::<Velocity>();
+        let mut previous = self.world.write_storage::<PreviousPosition>();
+
+        for (entity, pos, _vel) in (&entities, &positions, &velocities).join() {
+            previous.insert(entity, PreviousPosition { x: pos.x, y: pos.y }).ok();
+        }
+    }
+
+    /// Deletes every entity in the world and forgets any held input, so keys
+    /// pressed before a level load or mode change cannot stay stuck down.
+    pub fn clear(&mut self) {
+        self.world.delete_all();
+        if let Some(recorder) = &mut self.recorder {
+            for event in self.world.read_resource::<ActionState>().held_events() {
+                let released = match event {
+                    InputEvent::Action(action, _) => InputEvent::Action(action, false),
+                    InputEvent::Axis(axis, _) => InputEvent::Axis(axis, 0.0),
+                };
+                recorder.record(self.tick, released);
+            }
+        }
+        self.world.insert(ActionState::default());
+        self.pending.clear();
+        self.held.clear();
+        self.world.write_resource::<SpatialGrid>().clear();
+    }
+
+    /// Indexes the new level so the first step already sees its platforms,
+    /// and points the camera back at the start.
+    fn level_loaded(&mut self) {
+        SpatialIndexSystem::default().run_now(&self.world);
+        self.world.write_resource::<Camera>().reset();
+    }
+
+    /// Clears the world and loads a new level into it. On error the world is
+    /// left empty.
+    pub fn load_level(&mut self, level_data: &str) -> Result<LevelInfo, LevelError> {
+        self.clear();
+        let info = load_level(&mut self.world, level_data)?;
+        self.level_loaded();
+        Ok(info)
+    }
+
+    /// Clears the world and loads a level file into it.
+    pub fn load_level_file<P: AsRef<Path>>(&mut self, path: P) -> Result<LevelInfo, LevelError> {
+        self.clear();
+        let info = load_level_file(&mut self.world, path)?;
+        self.level_loaded();
+        Ok(info)
+    }
+
+    /// Clears the world and loads a map made in the Tiled editor, `.tmx` or
+    /// `.json`/`.tmj`.
+    pub fn load_tiled_file<P: AsRef<Path>>(&mut self, path: P) -> Result<LevelInfo, TiledError> {
+        self.clear();
+        let info = load_tiled_file(&mut self.world, path)?;
+        self.level_loaded();
+        Ok(info)
+    }
+
+    /// Adds the prefabs from a `.ron` or `.json` file to the `PrefabRegistry`
+    /// resource, replacing any with the same name.
+    pub fn load_prefabs<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PrefabError> {
+        let prefabs = PrefabRegistry::load(path)?;
+        self.world.write_resource::<PrefabRegistry>().extend(prefabs);
+        Ok(())
+    }
+
+    /// Header of the currently loaded level.
+    pub fn level_info(&self) -> LevelInfo {
+        (*self.world.read_resource::<LevelInfo>()).clone()
+    }
+
+    /// Extent of the currently loaded level.
+    pub fn world_bounds(&self) -> WorldBounds {
+        *self.world.read_resource::<WorldBounds>()
+    }
+
+    /// Returns true if any player overlaps a `Goal` entity.
+    pub fn player_reached_goal(&self) -> bool {
+        let positions = self.world.read_storage::<Position>();
+        let players = self.world.read_storage::<Player>();
+        let goals = self.world.read_storage::<Goal>();
+        let renderables = self.world.read_storage::<Renderable>();
+        let colliders = self.world.read_storage::<Collider>();
+        let entities = self.world.entities();
+        let shape = |entity| body_shape(positions.get(entity)?, colliders.get(entity), renderables.get(entity));
+
+        (&entities, &players).join().filter_map(|(player, _)| shape(player)).any(|player_shape| {
+            (&entities, &goals).join()
+                .filter_map(|(goal, _)| shape(goal))
+                .any(|goal_shape| shapes_overlap(player_shape, goal_shape))
+        })
+    }
+
+    /// Returns true if any player's health has run out.
+    pub fn player_dead(&self) -> bool {
+        let players = self.world.read_storage::<Player>();
+        let dead = self.world.read_storage::<Dead>();
+        (&players, &dead).join().next().is_some()
+    }
+
+    /// Returns true if any player has fallen below the given y coordinate.
+    pub fn player_below(&self, y: f32) -> bool {
+        let positions = self.world.read_storage::<Position>();
+        let players = self.world.read_storage::<Player>();
+        (&players, &positions).join().any(|(_player, pos)| pos.y > y)
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Assembles a `GameState` from the engine's built-in systems plus any
+/// game-specific systems.
+pub struct GameStateBuilder {
+    dispatcher: DispatcherBuilder<'static, 'static>,
+    timestep: FixedTimestep,
+    seed: u64,
+    input_map: InputMap,
+    viewport: (f32, f32),
+    prefabs: PrefabRegistry,
+    combat: CombatSettings,
+    mode: GameMode,
+}
+
+impl GameStateBuilder {
+    pub fn new() -> Self {
+        let dispatcher = DispatcherBuilder::new()
+            .with(PlayerControlSystem, "player_control", &[])
+            .with(EnemyAISystem, "enemy_ai", &[])
+            .with(CharacterControllerSystem, "character_controller", &["player_control"])
+            .with(MovingPlatformSystem, "moving_platform", &["character_controller", "enemy_ai"])
+            .with(MovementSystem, "movement", &["character_controller", "enemy_ai", "moving_platform"])
+            .with(SpatialIndexSystem::default(), "spatial_index", &["movement"])
+            .with(CollisionSystem::default(), "collision", &["movement", "spatial_index"])
+            .with(ParticleSystem, "particle", &["movement"])
+            .with(LogicSystem, "logic", &["movement"])
+            .with(ScoreSystem::default(), "score", &["collision"])
+            .with(DamageSystem::default(), "damage", &["collision"])
+            .with(HealthSystem, "health", &["damage"])
+            .with(CameraSystem, "camera", &["collision", "logic"]);
+
+        GameStateBuilder {
+            dispatcher,
+            timestep: FixedTimestep::default(),
+            seed: 0,
+            input_map: InputMap::default(),
+            viewport: (800.0, 600.0),
+            prefabs: PrefabRegistry::default(),
+            combat: CombatSettings::default(),
+            mode: GameMode::Menu,
+        }
+    }
+
+    /// Adds a system that runs after the built-in ones it depends on.
+    pub fn with_system<S>(mut self, system: S, name: &str, dependencies: &[&str]) -> Self
+    where
+        S: for<'a> System<'a> + Send + 'static,
+    {
+        self.dispatcher.add(system, name, dependencies);
+        self
+    }
+
+    /// Sets the simulation rate in steps per second (default 60).
+    pub fn with_fixed_rate(mut self, hz: f32) -> Self {
+        self.timestep = FixedTimestep::new(hz).with_max_steps(self.timestep.max_steps());
+        self
+    }
+
+    /// Caps how many simulation steps a single frame may run (default 5).
+    pub fn with_max_steps_per_frame(mut self, max_steps: u32) -> Self {
+        self.timestep = self.timestep.with_max_steps(max_steps);
+        self
+    }
+
+    /// Seeds the shared `GameRng` resource (default 0).
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the key bindings stored in the `InputMap` resource.
+    pub fn with_input_map(mut self, input_map: InputMap) -> Self {
+        self.input_map = input_map;
+        self
+    }
+
+    /// Sets the size of the camera's view in pixels (default 800x600).
+    pub fn with_viewport(mut self, width: f32, height: f32) -> Self {
+        self.viewport = (width, height);
+        self
+    }
+
+    /// Sets the prefabs levels and systems spawn from (default: the
+    /// built-in platform, player, enemy, coin, goal, decor, hint and
+    /// particle prefabs).
+    pub fn with_prefabs(mut self, prefabs: PrefabRegistry) -> Self {
+        self.prefabs = prefabs;
+        self
+    }
+
+    /// Sets contact damage, invulnerability time and knockback.
+    pub fn with_combat_settings(mut self, combat: CombatSettings) -> Self {
+        self.combat = combat;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: GameMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn build(self) -> GameState {
+        let mut world = World::new();
+        register_components(&mut world);
+        world.insert(GameRng::seeded(self.seed));
+        world.insert(self.input_map);
+        world.insert(ActionState::default());
+        world.insert(WorldBounds::default());
+        world.insert(PhysicsConfig::default());
+        world.insert(LevelInfo::default());
+        world.insert(Camera::new(self.viewport.0, self.viewport.1));
+        world.insert(self.prefabs);
+        world.insert(self.combat);
+        world.insert(SpatialGrid::default());
+
+        let mut dispatcher = self.dispatcher.build();
+        dispatcher.setup(&mut world);
+
+        GameState {
+            world,
+            dispatcher,
+            timestep: self.timestep,
+            tick: 0,
+            paused: false,
+            pending: Vec::new(),
+            held: HeldBindings::default(),
+            recorder: None,
+            mode: self.mode,
+        }
+    }
+}
+
+impl Default for GameStateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn register_components(world: &mut World) {
+    world.register::<Position>();
+    world.register::<Velocity>();
+    world.register::<Renderable>();
+    world.register::<Player>();
+    world.register::<CharacterController>();
+    world.register::<Collider>();
+    world.register::<ParticleEmitter>();
+    world.register::<Lifetime>();
+    world.register::<Collectible>();
+    world.register::<Gravity>();
+    world.register::<PhysicsMaterial>();
+    world.register::<OnMaterial>();
+    world.register::<Grounded>();
+    world.register::<Platform>();
+    world.register::<Enemy>();
+    world.register::<Health>();
+    world.register::<Goal>();
+    world.register::<PreviousPosition>();
+    world.register::<DrawOrder>();
+    world.register::<Damage>();
+    world.register::<Invulnerable>();
+    world.register::<Knockback>();
+    world.register::<Dead>();
+    world.register::<OneWay>();
+    world.register::<DropThrough>();
+    world.register::<MovingPlatform>();
+    world.register::<Slope>();
+    world.register::<OnSlope>();
+}
@@ -0,0 +1,125 @@
+use crate::engine::GameState;
+use crate::engine::replay::Replay;
+use crate::utils::input::{InputAction, InputAxis, InputEvent};
+
+/// A single scripted input change, applied at the start of `frame`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScriptedInput {
+    pub frame: u64,
+    pub event: InputEvent,
+}
+
+/// A timeline of input changes to feed into a `HeadlessRunner`.
+#[derive(Debug, Clone, Default)]
+pub struct InputScript {
+    inputs: Vec<ScriptedInput>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        InputScript::default()
+    }
+
+    pub fn press(mut self, frame: u64, action: InputAction) -> Self {
+        self.push(ScriptedInput { frame, event: InputEvent::Action(action, true) });
+        self
+    }
+
+    pub fn release(mut self, frame: u64, action: InputAction) -> Self {
+        self.push(ScriptedInput { frame, event: InputEvent::Action(action, false) });
+        self
+    }
+
+    /// Moves an analog axis to `value` at `frame`.
+    pub fn axis(mut self, frame: u64, axis: InputAxis, value: f32) -> Self {
+        self.push(ScriptedInput { frame, event: InputEvent::Axis(axis, value) });
+        self
+    }
+
+    /// Presses `action` at frame `from` and releases it at frame `to`.
+    pub fn hold(self, action: InputAction, from: u64, to: u64) -> Self {
+        self.press(from, action).release(to, action)
+    }
+
+    pub fn push(&mut self, input: ScriptedInput) {
+        // Keep inputs sorted by frame, preserving insertion order within a frame
+        let index = self.inputs.partition_point(|i| i.frame <= input.frame);
+        self.inputs.insert(index, input);
+    }
+
+    pub fn inputs(&self) -> &[ScriptedInput] {
+        &self.inputs
+    }
+}
+
+/// Steps a `GameState` without a window, applying scripted input per frame.
+pub struct HeadlessRunner {
+    pub state: GameState,
+    script: InputScript,
+    next_input: usize,
+    frame: u64,
+}
+
+impl HeadlessRunner {
+    pub fn new(state: GameState) -> Self {
+        HeadlessRunner {
+            state,
+            script: InputScript::new(),
+            next_input: 0,
+            frame: 0,
+        }
+    }
+
+    pub fn with_script(mut self, script: InputScript) -> Self {
+        self.script = script;
+        self.next_input = 0;
+        self
+    }
+
+    /// Number of frames simulated so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Queues input for the next frame, for bots that decide what to do as
+    /// they go.
+    pub fn input(&mut self, action: InputAction, pressed: bool) {
+        self.state.input(action, pressed);
+    }
+
+    /// Applies this frame's scripted input and advances the simulation by one
+    /// fixed step.
+    pub fn step(&mut self) {
+        while let Some(input) = self.script.inputs().get(self.next_input).copied() {
+            if input.frame > self.frame {
+                break;
+            }
+            self.state.input_event(input.event);
+            self.next_input += 1;
+        }
+
+        self.state.step();
+        self.frame += 1;
+    }
+
+    pub fn run(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Plays back a recorded session from the start. `self.state` should be
+    /// freshly built with the replay's seed, rate and level. Fails without
+    /// running anything if the state steps at a different rate.
+    pub fn play_replay(&mut self, replay: &Replay) -> Result<(), String> {
+        let rate = 1.0 / self.state.timestep().step();
+        if (rate - replay.rate).abs() > replay.rate * 1e-4 {
+            return Err(format!("replay was recorded at {} steps per second, but the state runs at {}", replay.rate, rate));
+        }
+        self.script = replay.to_script();
+        self.next_input = 0;
+        self.frame = 0;
+        self.run(replay.ticks);
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod game;
+pub mod headless;
+pub mod replay;
+pub mod time;
+
+pub use self::game::{GameMode, GameState, GameStateBuilder};
+pub use self::headless::{HeadlessRunner, InputScript, ScriptedInput};
+pub use self::replay::{InputRecorder, Replay};
+pub use self::time::FixedTimestep;
@@ -0,0 +1,136 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::engine::headless::{InputScript, ScriptedInput};
+use crate::utils::input::{InputAction, InputAxis, InputEvent};
+
+const HEADER: &str = "# rust_2d_engine replay v1";
+
+/// A recorded play session: the settings needed to rebuild the simulation
+/// plus every logical input change, stamped with the tick it applied on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    /// Simulation rate the session was recorded at, in steps per second.
+    pub rate: f32,
+    /// Free-form name of the level the session started on.
+    pub level: Option<String>,
+    /// Number of ticks the session lasted.
+    pub ticks: u64,
+    pub inputs: Vec<ScriptedInput>,
+}
+
+impl Replay {
+    /// Converts the recorded inputs into a script for a `HeadlessRunner`.
+    pub fn to_script(&self) -> InputScript {
+        let mut script = InputScript::new();
+        for input in &self.inputs {
+            script.push(*input);
+        }
+        script
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let text = fs::read_to_string(path)?;
+        Replay::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Parses the text format written by `save`. The rate must be positive
+    /// and finite.
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut replay = Replay { rate: 60.0, ..Replay::default() };
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let bad_value = |what: &str| format!("line {}: invalid {} in '{}'", line_no, what, line);
+            match fields.as_slice() {
+                ["seed", seed] => replay.seed = seed.parse().map_err(|_| bad_value("seed"))?,
+                ["rate", rate] => {
+                    replay.rate = rate.parse().ok().filter(|rate: &f32| *rate > 0.0 && rate.is_finite()).ok_or_else(|| bad_value("rate"))?;
+                },
+                ["ticks", ticks] => replay.ticks = ticks.parse().map_err(|_| bad_value("tick count"))?,
+                ["level", ..] => replay.level = Some(line["level".len()..].trim().to_string()),
+                [tick, name, state] => {
+                    let frame = tick.parse().map_err(|_| bad_value("tick"))?;
+                    let event = if let Ok(axis) = name.parse::<InputAxis>() {
+                        InputEvent::Axis(axis, state.parse().map_err(|_| bad_value("axis value"))?)
+                    } else {
+                        let action: InputAction = name.parse().map_err(|_| bad_value("action"))?;
+                        let pressed = match *state {
+                            "press" => true,
+                            "release" => false,
+                            _ => return Err(bad_value("press/release state")),
+                        };
+                        InputEvent::Action(action, pressed)
+                    };
+                    replay.inputs.push(ScriptedInput { frame, event });
+                },
+                _ => return Err(format!("line {}: unrecognised entry '{}'", line_no, line)),
+            }
+        }
+
+        Ok(replay)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "rate {}", self.rate)?;
+        if let Some(level) = &self.level {
+            writeln!(f, "level {}", level)?;
+        }
+        writeln!(f, "ticks {}", self.ticks)?;
+        for input in &self.inputs {
+            match input.event {
+                InputEvent::Action(action, pressed) => {
+                    let state = if pressed { "press" } else { "release" };
+                    writeln!(f, "{} {} {}", input.frame, action, state)?;
+                },
+                InputEvent::Axis(axis, value) => writeln!(f, "{} {} {}", input.frame, axis, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects the input a `GameState` applies while recording is active.
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    replay: Replay,
+    start_tick: u64,
+}
+
+impl InputRecorder {
+    pub fn new(seed: u64, rate: f32, level: Option<String>, start_tick: u64) -> Self {
+        InputRecorder {
+            replay: Replay { seed, rate, level, ticks: 0, inputs: Vec::new() },
+            start_tick,
+        }
+    }
+
+    /// Records an input applied on `tick`, stored relative to the start of
+    /// the recording.
+    pub fn record(&mut self, tick: u64, event: InputEvent) {
+        let frame = tick - self.start_tick;
+        self.replay.inputs.push(ScriptedInput { frame, event });
+    }
+
+    pub fn finish(mut self, tick: u64) -> Replay {
+        self.replay.ticks = tick - self.start_tick;
+        self.replay
+    }
+}
@@ -0,0 +1,59 @@
+/// Accumulator that turns variable frame times into a whole number of
+/// fixed simulation steps, plus the leftover fraction for rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    step: f32,
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Steps `hz` times per second. Panics unless `hz` is positive and finite.
+    pub fn new(hz: f32) -> Self {
+        assert!(hz > 0.0 && hz.is_finite(), "fixed rate must be positive and finite");
+        FixedTimestep { step: 1.0 / hz, max_steps: 5, accumulator: 0.0 }
+    }
+
+    /// Caps how many steps a single frame may run. Time beyond the cap is
+    /// dropped, so a long hitch slows the game down instead of spiralling.
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Length of one simulation step in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    /// Adds a frame's elapsed time and returns how many steps to simulate.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        if steps == self.max_steps && self.accumulator >= self.step {
+            self.accumulator = 0.0;
+        }
+        steps
+    }
+
+    /// How far the current frame is between the last step and the next one,
+    /// in `0.0..1.0`. Used to interpolate rendered positions.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        FixedTimestep::new(60.0)
+    }
+}
@@ -0,0 +1,52 @@
+use specs::Entity;
+
+/// Two entities overlapping or touching, as seen by `CollisionSystem` at
+/// the start of a step, before platform resolution. Touching only counts
+/// when one of the pair blocks the other; otherwise they must overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// The moving side of the pair: a body with a `Velocity` that blocks or
+    /// triggers on some layer. When both sides move, the lower entity.
+    pub a: Entity,
+    pub b: Entity,
+    /// Unit axis along which `a` would leave `b` fastest, pointing away
+    /// from `b`.
+    pub normal: (f32, f32),
+    /// Overlap along `normal`; zero for a body resting against something
+    /// that blocks it.
+    pub penetration: f32,
+}
+
+impl Contact {
+    /// The entity paired with `entity`, if it is part of this contact.
+    pub fn other(&self, entity: Entity) -> Option<Entity> {
+        if entity == self.a {
+            Some(self.b)
+        } else if entity == self.b {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+/// Published by `CollisionSystem` on the `EventChannel<CollisionEvent>`
+/// resource. Register a reader in a system's `setup` to react to contacts
+/// without adding to the collision code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CollisionEvent {
+    /// The pair started touching this step.
+    Started(Contact),
+    /// The pair stopped touching this step. Either entity may already have
+    /// been deleted.
+    Ended { a: Entity, b: Entity },
+}
+
+impl CollisionEvent {
+    pub fn entities(&self) -> (Entity, Entity) {
+        match *self {
+            CollisionEvent::Started(contact) => (contact.a, contact.b),
+            CollisionEvent::Ended { a, b } => (a, b),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Shapes and the intersection tests between them, shared by the collision
+//! system and the spatial grid's queries.
+
+/// Axis-aligned rectangle as `(x, y, width, height)`.
+pub type Rect = (f32, f32, f32, f32);
+
+/// A collision shape placed in the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Rect(Rect),
+    /// Circle around the centre `(x, y)`.
+    Circle { x: f32, y: f32, radius: f32 },
+}
+
+impl Shape {
+    /// Smallest rectangle containing the shape.
+    pub fn bounds(&self) -> Rect {
+        match *self {
+            Shape::Rect(rect) => rect,
+            Shape::Circle { x, y, radius } => (x - radius, y - radius, radius * 2.0, radius * 2.0),
+        }
+    }
+}
+
+pub fn check_aabb(r1: (f32, f32, f32, f32), r2: (f32, f32, f32, f32)) -> bool {
+    r1.0 < r2.0 + r2.2 &&
+    r1.0 + r1.2 > r2.0 &&
+    r1.1 < r2.1 + r2.3 &&
+    r1.1 + r1.3 > r2.1
+}
+
+/// Normal and penetration depth of two overlapping or edge-touching
+/// rectangles, or `None` if they are apart. The normal points from `r2`
+/// towards `r1` along the axis of least overlap.
+pub fn contact(r1: (f32, f32, f32, f32), r2: (f32, f32, f32, f32)) -> Option<((f32, f32), f32)> {
+    let overlap_x = (r1.0 + r1.2).min(r2.0 + r2.2) - r1.0.max(r2.0);
+    let overlap_y = (r1.1 + r1.3).min(r2.1 + r2.3) - r1.1.max(r2.1);
+    if overlap_x < 0.0 || overlap_y < 0.0 {
+        return None;
+    }
+
+    let away = |c1: f32, c2: f32| if c1 < c2 { -1.0 } else { 1.0 };
+    if overlap_x < overlap_y {
+        Some(((away(r1.0 + r1.2 / 2.0, r2.0 + r2.2 / 2.0), 0.0), overlap_x))
+    } else {
+        Some(((0.0, away(r1.1 + r1.3 / 2.0, r2.1 + r2.3 / 2.0)), overlap_y))
+    }
+}
+
+/// Like `contact`, for any pair of shapes: the normal pointing from `b`
+/// towards `a` and the penetration depth, or `None` if they are apart.
+/// Touching counts, with a depth of zero.
+pub fn shape_contact(a: Shape, b: Shape) -> Option<((f32, f32), f32)> {
+    match (a, b) {
+        (Shape::Rect(r1), Shape::Rect(r2)) => contact(r1, r2),
+        (Shape::Circle { x: x1, y: y1, radius: r1 }, Shape::Circle { x: x2, y: y2, radius: r2 }) => {
+            let (dx, dy) = (x1 - x2, y1 - y2);
+            let distance = (dx * dx + dy * dy).sqrt();
+            let penetration = r1 + r2 - distance;
+            if penetration < 0.0 {
+                return None;
+            }
+            // Concentric circles have no direction; push `a` up
+            let normal = if distance > 0.0 { (dx / distance, dy / distance) } else { (0.0, -1.0) };
+            Some((normal, penetration))
+        },
+        (Shape::Circle { x, y, radius }, Shape::Rect(rect)) => {
+            // Nearest point of the rectangle to the centre
+            let nearest = (x.clamp(rect.0, rect.0 + rect.2), y.clamp(rect.1, rect.1 + rect.3));
+            let (dx, dy) = (x - nearest.0, y - nearest.1);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance == 0.0 {
+                // Centre inside the rectangle: push out along the shallow axis
+                return contact(a.bounds(), rect);
+            }
+            if distance > radius {
+                return None;
+            }
+            Some(((dx / distance, dy / distance), radius - distance))
+        },
+        (Shape::Rect(_), Shape::Circle { .. }) => {
+            shape_contact(b, a).map(|(normal, penetration)| ((-normal.0, -normal.1), penetration))
+        },
+    }
+}
+
+/// Whether two shapes overlap by more than touching.
+pub fn shapes_overlap(a: Shape, b: Shape) -> bool {
+    shape_contact(a, b).is_some_and(|(_, penetration)| penetration > 0.0)
+}
+
+/// Time of impact of `moving` travelling by `delta` into the static
+/// `target`, as a fraction of `delta` in `0.0..=1.0`, with the normal of the
+/// face that was hit. Bodies that already overlap, only graze a corner or
+/// move apart do not hit.
+pub fn sweep_aabb(moving: Rect, delta: (f32, f32), target: Rect) -> Option<(f32, (f32, f32))> {
+    // Entry and exit times along one axis
+    let axis = |pos: f32, size: f32, d: f32, target_pos: f32, target_size: f32| {
+        if d > 0.0 {
+            Some(((target_pos - (pos + size)) / d, (target_pos + target_size - pos) / d))
+        } else if d < 0.0 {
+            Some(((target_pos + target_size - pos) / d, (target_pos - (pos + size)) / d))
+        } else if pos < target_pos + target_size && pos + size > target_pos {
+            Some((f32::NEG_INFINITY, f32::INFINITY))
+        } else {
+            None
+        }
+    };
+    let (entry_x, exit_x) = axis(moving.0, moving.2, delta.0, target.0, target.2)?;
+    let (entry_y, exit_y) = axis(moving.1, moving.3, delta.1, target.1, target.3)?;
+
+    let entry = entry_x.max(entry_y);
+    let exit = exit_x.min(exit_y);
+    if entry >= exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+
+    let normal = if entry_x > entry_y {
+        (-delta.0.signum(), 0.0)
+    } else {
+        (0.0, -delta.1.signum())
+    };
+    Some((entry, normal))
+}
+
+/// Distance along a ray from `origin` in the unit `direction` to where it
+/// enters `shape`, with the surface normal there. Rays starting inside the
+/// shape, or pointing away from it, do not hit.
+pub fn ray_shape(origin: (f32, f32), direction: (f32, f32), shape: Shape) -> Option<(f32, (f32, f32))> {
+    match shape {
+        Shape::Rect(rect) => {
+            // Entry and exit distances between the two edges on one axis
+            let axis = |pos: f32, d: f32, min: f32, size: f32| {
+                if d != 0.0 {
+                    let (a, b) = ((min - pos) / d, (min + size - pos) / d);
+                    Some((a.min(b), a.max(b)))
+                } else if pos >= min && pos <= min + size {
+                    Some((f32::NEG_INFINITY, f32::INFINITY))
+                } else {
+                    None
+                }
+            };
+            let (entry_x, exit_x) = axis(origin.0, direction.0, rect.0, rect.2)?;
+            let (entry_y, exit_y) = axis(origin.1, direction.1, rect.1, rect.3)?;
+            let (entry, exit) = (entry_x.max(entry_y), exit_x.min(exit_y));
+            if entry > exit || entry < 0.0 {
+                return None;
+            }
+            let normal = if entry_x > entry_y { (-direction.0.signum(), 0.0) } else { (0.0, -direction.1.signum()) };
+            Some((entry, normal))
+        },
+        Shape::Circle { x, y, radius } => {
+            let to_centre = (x - origin.0, y - origin.1);
+            let along = to_centre.0 * direction.0 + to_centre.1 * direction.1;
+            let outside = to_centre.0 * to_centre.0 + to_centre.1 * to_centre.1 - radius * radius;
+            let discriminant = along * along - outside;
+            if outside < 0.0 || along < 0.0 || discriminant < 0.0 {
+                return None;
+            }
+            let distance = along - discriminant.sqrt();
+            let point = (origin.0 + direction.0 * distance, origin.1 + direction.1 * distance);
+            Some((distance, ((point.0 - x) / radius, (point.1 - y) / radius)))
+        },
+    }
+}
@@ -0,0 +1,12 @@
+pub mod components;
+pub mod engine;
+pub mod events;
+pub mod geometry;
+pub mod prefab;
+pub mod resources;
+pub mod spatial;
+pub mod systems;
+pub mod utils;
+
+pub use crate::engine::{GameMode, GameState, GameStateBuilder, HeadlessRunner, InputScript, Replay};
+pub use crate::prefab::{spawn_prefab, PrefabRegistry};
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use specs::{Builder, Entity, World, WorldExt};
+
+use crate::components::*;
+
+/// Tile size, in pixels, that prefab sizes are given for. ASCII levels with
+/// another `tile_size` scale their prefabs to match.
+pub const BASE_TILE_SIZE: f32 = 40.0;
+
+/// Prefabs that are always available, in the same RON format as
+/// `assets/prefabs.ron`. Files loaded later can override or extend them.
+/// Sizes are for `BASE_TILE_SIZE` tiles.
+const BUILTIN_PREFABS: &str = r#"{
+    "platform": (components: [
+        Renderable(width: 40.0, height: 40.0, color: (100, 100, 100)),
+        Platform,
+    ]),
+    "ledge": (components: [
+        Renderable(width: 40.0, height: 10.0, color: (160, 120, 80)),
+        Platform,
+        OneWay,
+    ]),
+    "half_platform": (components: [
+        Renderable(width: 40.0, height: 20.0, color: (100, 100, 100)),
+        Platform,
+    ]),
+    "ice": (parent: "platform", components: [
+        Renderable(width: 40.0, height: 40.0, color: (170, 220, 255)),
+        PhysicsMaterial(friction: 0.1),
+    ]),
+    "mud": (parent: "platform", components: [
+        Renderable(width: 40.0, height: 40.0, color: (110, 75, 40)),
+        PhysicsMaterial(friction: 2.0, speed: 0.5),
+    ]),
+    "bounce_pad": (parent: "platform", components: [
+        Renderable(width: 40.0, height: 40.0, color: (255, 100, 200)),
+        PhysicsMaterial(bounciness: 0.8),
+    ]),
+    "slope_up": (parent: "platform", components: [
+        Slope(left: 0.0, right: 1.0),
+    ]),
+    "slope_down": (parent: "platform", components: [
+        Slope(left: 1.0, right: 0.0),
+    ]),
+    "slope_up_low": (parent: "platform", components: [
+        Slope(left: 0.0, right: 0.5),
+    ]),
+    "slope_up_high": (parent: "platform", components: [
+        Slope(left: 0.5, right: 1.0),
+    ]),
+    "slope_down_high": (parent: "platform", components: [
+        Slope(left: 1.0, right: 0.5),
+    ]),
+    "slope_down_low": (parent: "platform", components: [
+        Slope(left: 0.5, right: 0.0),
+    ]),
+    "moving_platform": (components: [
+        Renderable(width: 80.0, height: 20.0, color: (120, 120, 160)),
+        Velocity(x: 0.0, y: 0.0),
+        Platform,
+        MovingPlatform(path: [(120.0, 0.0)], speed: 80.0, wait: 0.5),
+    ]),
+    "player": (components: [
+        Renderable(width: 40.0, height: 40.0, color: (0, 255, 0)),
+        Velocity(x: 0.0, y: 0.0),
+        Player(speed: 200.0, jump_force: 600.0),
+        CharacterController(),
+        Health(max: 100),
+        Gravity(scale: 1.0),
+    ]),
+    "enemy": (components: [
+        Renderable(width: 40.0, height: 40.0, color: (255, 0, 0)),
+        Velocity(x: 100.0, y: 0.0),
+        Enemy,
+        Damage(amount: 20),
+        Gravity(scale: 1.0),
+    ]),
+    "coin": (components: [
+        Renderable(width: 20.0, height: 20.0, color: (255, 215, 0)),
+        Collider(shape: Circle(radius: 10.0)),
+        Collectible,
+    ]),
+    "goal": (components: [
+        Renderable(width: 40.0, height: 60.0, color: (0, 255, 255)),
+        Goal,
+    ]),
+    "decor": (components: [
+        Renderable(width: 40.0, height: 40.0, color: (150, 150, 150)),
+    ]),
+    "hint": (parent: "decor", components: [
+        Renderable(width: 40.0, height: 40.0, color: (200, 200, 255)),
+    ]),
+    "particle": (components: [
+        Renderable(width: 4.0, height: 4.0, color: (255, 255, 255)),
+        Collider(shape: Aabb(width: 4.0, height: 4.0), layers: ["particle"], blocks: ["platform"], triggers: []),
+        Velocity(x: 0.0, y: 0.0),
+        Lifetime(remaining: 1.0),
+    ]),
+}"#;
+
+/// Serializable description of one component and its starting values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ComponentDef {
+    Renderable { width: f32, height: f32, color: (u8, u8, u8) },
+    Velocity { x: f32, y: f32 },
+    Player { speed: f32, jump_force: f32 },
+    CharacterController(ControllerTuning),
+    Health { max: i32 },
+    Damage { amount: i32 },
+    Collider {
+        shape: ColliderShape,
+        #[serde(default)]
+        offset: (f32, f32),
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        layers: Option<Layers>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        blocks: Option<Layers>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        triggers: Option<Layers>,
+    },
+    ParticleEmitter { rate: f32, lifetime: f32, color: (u8, u8, u8) },
+    Lifetime { remaining: f32 },
+    Gravity {
+        #[serde(default = "default_gravity_scale")]
+        scale: f32,
+    },
+    PhysicsMaterial(PhysicsMaterial),
+    Platform,
+    OneWay,
+    Slope { left: f32, right: f32 },
+    MovingPlatform {
+        path: Vec<(f32, f32)>,
+        speed: f32,
+        #[serde(default)]
+        wait: f32,
+        #[serde(default)]
+        mode: PathMode,
+    },
+    Enemy,
+    Collectible,
+    Goal,
+}
+
+impl ComponentDef {
+    /// Adds the described component to an entity under construction. Works
+    /// with both `EntityBuilder` and `LazyBuilder`.
+    pub fn add_to<B: Builder>(&self, builder: B) -> B {
+        match *self {
+            ComponentDef::Renderable { width, height, color } => builder.with(Renderable { width, height, color }),
+            ComponentDef::Velocity { x, y } => builder.with(Velocity { x, y }),
+            ComponentDef::Player { speed, jump_force } => builder.with(Player { speed, jump_force, score: 0 }),
+            ComponentDef::CharacterController(tuning) => builder.with(CharacterController::new(tuning)),
+            ComponentDef::Health { max } => builder.with(Health { current: max, max }),
+            ComponentDef::Damage { amount } => builder.with(Damage { amount }),
+            ComponentDef::Collider { shape, offset, layers, blocks, triggers } => builder.with(Collider { shape, offset, layers, blocks, triggers }),
+            ComponentDef::ParticleEmitter { rate, lifetime, color } => builder.with(ParticleEmitter { rate, lifetime, color }),
+            ComponentDef::Lifetime { remaining } => builder.with(Lifetime { remaining }),
+            ComponentDef::Gravity { scale } => builder.with(Gravity { scale }),
+            ComponentDef::PhysicsMaterial(material) => builder.with(material),
+            ComponentDef::Platform => builder.with(Platform),
+            ComponentDef::OneWay => builder.with(OneWay),
+            ComponentDef::Slope { left, right } => builder.with(Slope { left, right }),
+            ComponentDef::MovingPlatform { ref path, speed, wait, mode } => builder.with(MovingPlatform::new(path.clone(), speed, wait, mode)),
+            ComponentDef::Enemy => builder.with(Enemy),
+            ComponentDef::Collectible => builder.with(Collectible),
+            ComponentDef::Goal => builder.with(Goal),
+        }
+    }
+
+    /// Multiplies the component's sizes, offsets and path lengths by
+    /// `factor`. Speeds and other values are left alone.
+    pub fn scale(&mut self, factor: f32) {
+        match self {
+            ComponentDef::Renderable { width, height, .. } => {
+                *width *= factor;
+                *height *= factor;
+            },
+            ComponentDef::Collider { shape, offset, .. } => {
+                *shape = match *shape {
+                    ColliderShape::Aabb { width, height } => ColliderShape::Aabb { width: width * factor, height: height * factor },
+                    ColliderShape::Circle { radius } => ColliderShape::Circle { radius: radius * factor },
+                };
+                *offset = (offset.0 * factor, offset.1 * factor);
+            },
+            ComponentDef::MovingPlatform { path, .. } => {
+                for point in path.iter_mut() {
+                    *point = (point.0 * factor, point.1 * factor);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn same_kind(&self, other: &ComponentDef) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+}
+
+fn default_gravity_scale() -> f32 {
+    1.0
+}
+
+/// A named entity archetype: a component list, optionally layered on top of
+/// a parent prefab. A child's component replaces the parent's component of
+/// the same kind.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrefabDef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub components: Vec<ComponentDef>,
+}
+
+#[derive(Debug)]
+pub enum PrefabError {
+    Io(io::Error),
+    Parse(String),
+    Unknown(String),
+    /// The named prefab (transitively) inherits from itself.
+    Cycle(String),
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefabError::Io(e) => write!(f, "could not read prefabs: {}", e),
+            PrefabError::Parse(message) => write!(f, "invalid prefab file: {}", message),
+            PrefabError::Unknown(name) => write!(f, "unknown prefab '{}'", name),
+            PrefabError::Cycle(name) => write!(f, "prefab '{}' inherits from itself", name),
+        }
+    }
+}
+
+impl std::error::Error for PrefabError {}
+
+impl From<io::Error> for PrefabError {
+    fn from(e: io::Error) -> Self {
+        PrefabError::Io(e)
+    }
+}
+
+/// Every prefab known to the game, stored as a `World` resource.
+#[derive(Debug, Clone)]
+pub struct PrefabRegistry {
+    prefabs: HashMap<String, PrefabDef>,
+}
+
+impl PrefabRegistry {
+    pub fn empty() -> Self {
+        PrefabRegistry { prefabs: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, name: &str, prefab: PrefabDef) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PrefabDef> {
+        self.prefabs.get(name)
+    }
+
+    /// Parses a RON map of prefab name to `PrefabDef`. `parent` may be
+    /// written as a plain string rather than `Some("...")`, and tuning
+    /// structs without their own parentheses, e.g.
+    /// `CharacterController(coyote_time: 0.15)`.
+    pub fn parse_ron(text: &str) -> Result<PrefabRegistry, PrefabError> {
+        let prefabs = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME | ron::extensions::Extensions::UNWRAP_VARIANT_NEWTYPES)
+            .from_str(text)
+            .map_err(|e| PrefabError::Parse(e.to_string()))?;
+        Ok(PrefabRegistry { prefabs })
+    }
+
+    /// Parses a JSON object of prefab name to `PrefabDef`.
+    pub fn parse_json(text: &str) -> Result<PrefabRegistry, PrefabError> {
+        let prefabs = serde_json::from_str(text).map_err(|e| PrefabError::Parse(e.to_string()))?;
+        Ok(PrefabRegistry { prefabs })
+    }
+
+    /// Loads a `.ron` or `.json` prefab file, chosen by extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<PrefabRegistry, PrefabError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => PrefabRegistry::parse_json(&text),
+            _ => PrefabRegistry::parse_ron(&text),
+        }
+    }
+
+    /// Adds every prefab of `other`, replacing existing ones with the same name.
+    pub fn extend(&mut self, other: PrefabRegistry) {
+        self.prefabs.extend(other.prefabs);
+    }
+
+    /// Flattens a prefab's inheritance chain into its final component list.
+    pub fn resolve(&self, name: &str) -> Result<Vec<ComponentDef>, PrefabError> {
+        // Walk up to the root, then apply each level from the root down
+        let mut chain = Vec::new();
+        let mut current = Some(name);
+        while let Some(prefab_name) = current {
+            if chain.iter().any(|(seen, _)| *seen == prefab_name) {
+                return Err(PrefabError::Cycle(name.to_string()));
+            }
+            let prefab = self.get(prefab_name).ok_or_else(|| PrefabError::Unknown(prefab_name.to_string()))?;
+            chain.push((prefab_name, prefab));
+            current = prefab.parent.as_deref();
+        }
+
+        let mut components: Vec<ComponentDef> = Vec::new();
+        for (_, prefab) in chain.iter().rev() {
+            for component in &prefab.components {
+                match components.iter_mut().find(|existing| existing.same_kind(component)) {
+                    Some(existing) => *existing = component.clone(),
+                    None => components.push(component.clone()),
+                }
+            }
+        }
+        Ok(components)
+    }
+
+    /// Adds a prefab's components to an entity under construction, so the
+    /// caller can chain further `.with` calls before building.
+    pub fn apply<B: Builder>(&self, name: &str, builder: B) -> Result<B, PrefabError> {
+        Ok(self.resolve(name)?.iter().fold(builder, |builder, component| component.add_to(builder)))
+    }
+}
+
+impl Default for PrefabRegistry {
+    fn default() -> Self {
+        PrefabRegistry::parse_ron(BUILTIN_PREFABS).expect("built-in prefabs are valid RON")
+    }
+}
+
+/// Spawns the named prefab from the world's `PrefabRegistry` at `pos`.
+pub fn spawn_prefab(world: &mut World, name: &str, pos: (f32, f32)) -> Result<Entity, PrefabError> {
+    let components = world.read_resource::<PrefabRegistry>().resolve(name)?;
+    Ok(spawn_components(world, &components, pos))
+}
+
+/// Spawns an already resolved component list at `pos`.
+pub fn spawn_components(world: &mut World, components: &[ComponentDef], pos: (f32, f32)) -> Entity {
+    let builder = world.create_entity().with(Position { x: pos.0, y: pos.1 });
+    components.iter()
+        .fold(builder, |builder, component| component.add_to(builder))
+        .build()
+}
@@ -0,0 +1,223 @@
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Seeded random number generator shared by all systems through the `World`.
+/// Systems must draw randomness from here rather than `thread_rng` so that a
+/// run can be reproduced from its seed.
+pub struct GameRng {
+    rng: StdRng,
+    seed: u64,
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        GameRng { rng: StdRng::seed_from_u64(seed), seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::seeded(0)
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Extent of the loaded level in world units, computed by `load_level`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldBounds {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl WorldBounds {
+    pub fn new(width: f32, height: f32) -> Self {
+        WorldBounds { min_x: 0.0, min_y: 0.0, max_x: width, max_y: height }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        WorldBounds::new(800.0, 600.0)
+    }
+}
+
+/// Physics of the loaded level, read from the level header by `load_level`
+/// and from map properties by the Tiled loader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsConfig {
+    /// Acceleration of airborne bodies with `Gravity`, in px/s².
+    pub gravity: (f32, f32),
+    /// Share of horizontal speed a body keeps every 1/60 s on the ground.
+    pub ground_drag: f32,
+    /// The same in the air.
+    pub air_drag: f32,
+    /// Horizontal speed below which drag brings a body to a stop.
+    pub rest_speed: f32,
+    /// Players falling below this are respawned. `None` puts it 100px below
+    /// the bottom of the level.
+    pub kill_y: Option<f32>,
+}
+
+impl PhysicsConfig {
+    pub fn kill_y(&self, bounds: &WorldBounds) -> f32 {
+        self.kill_y.unwrap_or(bounds.max_y + 100.0)
+    }
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: (0.0, 1500.0),
+            ground_drag: 0.85,
+            air_drag: 0.95,
+            rest_speed: 5.0,
+            kill_y: None,
+        }
+    }
+}
+
+/// Scrolling view onto the world. `x`/`y` is the world position of the
+/// top-left corner of the viewport.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    prev_x: f32,
+    prev_y: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+    /// Size of the box around the view center the target can move in
+    /// without the camera following.
+    pub dead_zone: (f32, f32),
+    /// How far ahead of a moving target, horizontally, the camera aims.
+    pub look_ahead: f32,
+    /// Catch-up rate per second; higher values follow more tightly.
+    pub smoothing: f32,
+    needs_snap: bool,
+}
+
+impl Camera {
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Camera {
+            x: 0.0,
+            y: 0.0,
+            prev_x: 0.0,
+            prev_y: 0.0,
+            viewport_width,
+            viewport_height,
+            dead_zone: (80.0, 60.0),
+            look_ahead: 60.0,
+            smoothing: 6.0,
+            needs_snap: true,
+        }
+    }
+
+    /// Makes the camera jump straight to its target on the next update,
+    /// e.g. after a level load.
+    pub fn reset(&mut self) {
+        self.needs_snap = true;
+    }
+
+    pub fn needs_snap(&self) -> bool {
+        self.needs_snap
+    }
+
+    /// Moves the camera, remembering the old position for interpolation.
+    pub fn move_to(&mut self, x: f32, y: f32, snap: bool) {
+        if snap {
+            self.prev_x = x;
+            self.prev_y = y;
+            self.needs_snap = false;
+        } else {
+            self.prev_x = self.x;
+            self.prev_y = self.y;
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    /// Camera position interpolated between the last two steps.
+    pub fn interpolated(&self, alpha: f32) -> (f32, f32) {
+        (self.prev_x + (self.x - self.prev_x) * alpha, self.prev_y + (self.y - self.prev_y) * alpha)
+    }
+
+    pub fn world_to_screen(&self, x: f32, y: f32, alpha: f32) -> (f32, f32) {
+        let (cam_x, cam_y) = self.interpolated(alpha);
+        (x - cam_x, y - cam_y)
+    }
+
+    pub fn screen_to_world(&self, x: f32, y: f32, alpha: f32) -> (f32, f32) {
+        let (cam_x, cam_y) = self.interpolated(alpha);
+        (x + cam_x, y + cam_y)
+    }
+
+    /// Keeps the view inside `bounds`. Levels smaller than the viewport stay
+    /// pinned to their top-left corner.
+    pub fn clamp_to(&self, x: f32, y: f32, bounds: &WorldBounds) -> (f32, f32) {
+        let max_x = (bounds.max_x - self.viewport_width).max(bounds.min_x);
+        let max_y = (bounds.max_y - self.viewport_height).max(bounds.min_y);
+        (x.clamp(bounds.min_x, max_x), y.clamp(bounds.min_y, max_y))
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new(800.0, 600.0)
+    }
+}
+
+/// Tuning for contact damage between players and enemies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CombatSettings {
+    /// Damage dealt by enemies without a `Damage` component.
+    pub contact_damage: i32,
+    /// Seconds a player ignores further damage after a hit.
+    pub invulnerability: f32,
+    /// Velocity applied on a hit, pushing away from the enemy horizontally
+    /// and upwards by `knockback.1`.
+    pub knockback: (f32, f32),
+    /// Seconds player input is ignored after a hit.
+    pub knockback_time: f32,
+}
+
+impl Default for CombatSettings {
+    fn default() -> Self {
+        CombatSettings {
+            contact_damage: 20,
+            invulnerability: 1.0,
+            knockback: (250.0, 300.0),
+            knockback_time: 0.2,
+        }
+    }
+}
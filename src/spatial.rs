@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet};
+
+use specs::world::Index;
+use specs::Entity;
+
+use crate::components::{CollisionFilter, Layers};
+use crate::geometry::{ray_shape, sweep_aabb, Rect, Shape};
+
+/// Uniform grid over the world for finding entities near a rectangle or
+/// point without scanning every entity. Stored as a `World` resource and
+/// kept up to date by `SpatialIndexSystem`.
+///
+/// Each entity is stored in every cell its rectangle covers, along with its
+/// `CollisionFilter`. Updating an entity whose rectangle has not changed
+/// leaves its cells alone.
+///
+/// Besides overlap queries, the grid answers ray casts, segment casts and
+/// box sweeps, so any system can look along a line without scanning
+/// entities itself.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+    /// Keyed by entity index: at most one generation of an index is stored.
+    shapes: HashMap<Index, (Entity, Shape, CollisionFilter)>,
+    /// Lowest and highest cell anything was stored in. Only grows until
+    /// `clear`, so it may cover cells that have since emptied.
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+/// Where a ray or segment first hit something.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: (f32, f32),
+    /// Surface normal at `point`, facing back along the ray.
+    pub normal: (f32, f32),
+    /// Distance from the ray's origin to `point`.
+    pub distance: f32,
+}
+
+/// Where a swept box first hit something.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepHit {
+    pub entity: Entity,
+    /// Top-left corner of the box when it touches.
+    pub position: (f32, f32),
+    /// Normal of the face that was hit.
+    pub normal: (f32, f32),
+    /// Fraction of the sweep travelled before the hit, in `0.0..=1.0`.
+    pub time: f32,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        SpatialGrid { cell_size, cells: HashMap::new(), shapes: HashMap::new(), bounds: None }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Number of entities in the grid.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Bounding box of the shape `entity` was last stored with.
+    pub fn rect(&self, entity: Entity) -> Option<Rect> {
+        self.stored(entity).map(|(shape, _)| shape.bounds())
+    }
+
+    /// The shape `entity` was last stored with.
+    pub fn shape(&self, entity: Entity) -> Option<Shape> {
+        self.stored(entity).map(|(shape, _)| shape)
+    }
+
+    /// The filter `entity` was last stored with.
+    pub fn filter(&self, entity: Entity) -> Option<CollisionFilter> {
+        self.stored(entity).map(|(_, filter)| filter)
+    }
+
+    fn stored(&self, entity: Entity) -> Option<(Shape, CollisionFilter)> {
+        match self.shapes.get(&entity.id()) {
+            Some(&(stored, shape, filter)) if stored == entity => Some((shape, filter)),
+            _ => None,
+        }
+    }
+
+    /// Adds `entity` or moves it to `rect`, on every layer and blocking or
+    /// triggering nothing.
+    pub fn update(&mut self, entity: Entity, rect: Rect) {
+        let filter = CollisionFilter { layers: Layers::ALL, blocks: Layers::NONE, triggers: Layers::NONE };
+        self.update_filtered(entity, rect, filter);
+    }
+
+    /// Adds `entity` or moves it to `rect`, with its collision filter.
+    pub fn update_filtered(&mut self, entity: Entity, rect: Rect, filter: CollisionFilter) {
+        self.update_shape(entity, Shape::Rect(rect), filter);
+    }
+
+    /// Adds `entity` or moves it to `shape`, with its collision filter.
+    /// Overlap queries use the shape's bounding box; ray casts hit the exact
+    /// shape. Replaces any older entity with the same index.
+    pub fn update_shape(&mut self, entity: Entity, shape: Shape, filter: CollisionFilter) {
+        match self.shapes.get_mut(&entity.id()) {
+            Some((stored, old, old_filter)) if *stored == entity && *old == shape => {
+                *old_filter = filter;
+                return;
+            },
+            Some(_) => self.remove_index(entity.id()),
+            None => {},
+        }
+        let rect = shape.bounds();
+        let (low, high) = (self.cell(rect.0, rect.1), self.cell(rect.0 + rect.2, rect.1 + rect.3));
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => ((min.0.min(low.0), min.1.min(low.1)), (max.0.max(high.0), max.1.max(high.1))),
+            None => (low, high),
+        });
+        for cell in self.cells_for(shape.bounds()) {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.shapes.insert(entity.id(), (entity, shape, filter));
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if self.stored(entity).is_some() {
+            self.remove_index(entity.id());
+        }
+    }
+
+    /// Removes whichever entity with index `id` is stored, for when only
+    /// the index of a deleted entity is known.
+    pub fn remove_index(&mut self, id: Index) {
+        let (entity, rect) = match self.shapes.remove(&id) {
+            Some((entity, shape, _)) => (entity, shape.bounds()),
+            None => return,
+        };
+        for cell in self.cells_for(rect) {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|e| *e != entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Removes every entity for which `keep` returns false.
+    pub fn retain<F: FnMut(Entity) -> bool>(&mut self, mut keep: F) {
+        let stale: Vec<Entity> = self.shapes.values().map(|(entity, _, _)| *entity).filter(|entity| !keep(*entity)).collect();
+        for entity in stale {
+            self.remove(entity);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.shapes.clear();
+        self.bounds = None;
+    }
+
+    /// Entities whose rectangle overlaps or touches `rect`, in entity order.
+    pub fn query_aabb(&self, rect: Rect) -> Vec<Entity> {
+        let mut found: Vec<Entity> = self.cells_for(rect)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|entity| self.rect(*entity).is_some_and(|other| touches(rect, other)))
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Like `query_aabb`, keeping only entities on one of `layers`.
+    pub fn query_layers(&self, rect: Rect, layers: Layers) -> Vec<Entity> {
+        let mut found = self.query_aabb(rect);
+        found.retain(|entity| self.filter(*entity).is_some_and(|filter| filter.layers.intersects(layers)));
+        found
+    }
+
+    /// Entities whose rectangle contains the point. Left and top edges are
+    /// inside, right and bottom edges are not.
+    pub fn query_point(&self, x: f32, y: f32) -> Vec<Entity> {
+        let mut found: Vec<Entity> = self.cells.get(&self.cell(x, y))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|entity| self.rect(*entity).is_some_and(|r| x >= r.0 && x < r.0 + r.2 && y >= r.1 && y < r.1 + r.3))
+            .collect();
+        found.sort();
+        found
+    }
+
+    /// First entity on one of `layers` hit by a ray from `origin` along
+    /// `direction`, at most `max_distance` away. Shapes the ray starts inside
+    /// are ignored, so a body can cast from within itself. Ties go to the
+    /// lowest entity.
+    ///
+    /// Walks the cells the ray passes through in order, stopping at the
+    /// first cell that holds a hit, so short hits are cheap however far the
+    /// ray could reach. `max_distance` may be infinite: the walk ends where
+    /// the ray leaves the occupied cells. A NaN or non-finite origin or
+    /// direction hits nothing.
+    pub fn raycast(&self, origin: (f32, f32), direction: (f32, f32), max_distance: f32, layers: Layers) -> Option<RayHit> {
+        let length = direction.0.hypot(direction.1);
+        if length == 0.0 || !length.is_finite() || !origin.0.is_finite() || !origin.1.is_finite() || max_distance.is_nan() {
+            return None;
+        }
+        let direction = (direction.0 / length, direction.1 / length);
+        let (enter, leave) = self.occupied_span(origin, direction, (0.0, 0.0))?;
+        let max_distance = max_distance.min(leave);
+        if enter > max_distance {
+            return None;
+        }
+
+        // Start where the ray enters the occupied cells. Far-off origins
+        // lose precision there, so the walk is kept inside them
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds?;
+        let start = self.cell(origin.0 + direction.0 * enter, origin.1 + direction.1 * enter);
+        let mut cell = (start.0.clamp(low_x, high_x), start.1.clamp(low_y, high_y));
+        let step = (step_towards(direction.0), step_towards(direction.1));
+        // Distance along the ray to the next cell edge on each axis, and
+        // between two edges
+        let edge = |pos: f32, d: f32, cell: i32| match step_towards(d) {
+            1 => ((cell + 1) as f32 * self.cell_size - pos) / d,
+            -1 => (cell as f32 * self.cell_size - pos) / d,
+            _ => f32::INFINITY,
+        };
+        let mut next = (edge(origin.0, direction.0, cell.0), edge(origin.1, direction.1, cell.1));
+        let across = (self.cell_size / direction.0.abs(), self.cell_size / direction.1.abs());
+
+        let mut checked = HashSet::new();
+        let mut best: Option<RayHit> = None;
+        loop {
+            for &entity in self.cells.get(&cell).into_iter().flatten() {
+                if !checked.insert(entity) || !self.filter(entity).is_some_and(|filter| filter.layers.intersects(layers)) {
+                    continue;
+                }
+                let hit = self.shape(entity).and_then(|shape| ray_shape(origin, direction, shape));
+                if let Some((distance, normal)) = hit.filter(|(distance, _)| *distance <= max_distance) {
+                    let point = (origin.0 + direction.0 * distance, origin.1 + direction.1 * distance);
+                    let hit = RayHit { entity, point, normal, distance };
+                    if best.is_none_or(|best| (hit.distance, hit.entity) < (best.distance, best.entity)) {
+                        best = Some(hit);
+                    }
+                }
+            }
+            // Every hit nearer than where the ray leaves this cell is found.
+            // One exactly on the edge may tie with a lower entity beyond it
+            let leave = next.0.min(next.1);
+            if best.is_some_and(|hit| hit.distance < leave) || leave > max_distance {
+                return best;
+            }
+            if next.0 < next.1 {
+                cell.0 += step.0;
+                next.0 += across.0;
+            } else {
+                cell.1 += step.1;
+                next.1 += across.1;
+            }
+            if !(low_x..=high_x).contains(&cell.0) || !(low_y..=high_y).contains(&cell.1) {
+                return best;
+            }
+        }
+    }
+
+    /// First entity on one of `layers` between `from` and `to`.
+    pub fn segment_cast(&self, from: (f32, f32), to: (f32, f32), layers: Layers) -> Option<RayHit> {
+        let delta = (to.0 - from.0, to.1 - from.1);
+        self.raycast(from, delta, delta.0.hypot(delta.1), layers)
+    }
+
+    /// Whether nothing on `layers` lies between `from` and `to`.
+    pub fn line_of_sight(&self, from: (f32, f32), to: (f32, f32), layers: Layers) -> bool {
+        self.segment_cast(from, to, layers).is_none()
+    }
+
+    /// First entity on one of `layers` that `rect` would hit moving by
+    /// `delta`, against the bounding box of each shape. Entities `rect`
+    /// already overlaps are ignored, as in `sweep_aabb`. Ties go to the
+    /// lowest entity.
+    ///
+    /// Walks the part of the path over occupied cells at most a cell at a
+    /// time, stopping at the first stretch that holds a hit. A NaN or
+    /// non-finite `rect` or `delta` hits nothing.
+    pub fn sweep(&self, rect: Rect, delta: (f32, f32), layers: Layers) -> Option<SweepHit> {
+        let length = delta.0.hypot(delta.1);
+        if length == 0.0 || !length.is_finite() || ![rect.0, rect.1, rect.2, rect.3].iter().all(|v| v.is_finite()) {
+            return None;
+        }
+        let direction = (delta.0 / length, delta.1 / length);
+        let (enter, leave) = self.occupied_span((rect.0, rect.1), direction, (rect.2, rect.3))?;
+        let (first, last) = (enter / length, (leave / length).min(1.0));
+        if first > last {
+            return None;
+        }
+        let stretches = ((last - first) * length / self.cell_size).ceil().max(1.0) as u32;
+        let mut checked = HashSet::new();
+        let mut best: Option<SweepHit> = None;
+        for stretch in 0..stretches {
+            let along = |stretch: u32| first + (last - first) * stretch as f32 / stretches as f32;
+            let (from, to) = (along(stretch), along(stretch + 1));
+            let start = (rect.0 + delta.0 * from, rect.1 + delta.1 * from);
+            let end = (rect.0 + delta.0 * to, rect.1 + delta.1 * to);
+            let area = (start.0.min(end.0), start.1.min(end.1), rect.2 + (end.0 - start.0).abs(), rect.3 + (end.1 - start.1).abs());
+            for entity in self.query_layers(area, layers) {
+                if !checked.insert(entity) {
+                    continue;
+                }
+                let hit = self.rect(entity).and_then(|target| sweep_aabb(rect, delta, target));
+                if let Some((time, normal)) = hit {
+                    let position = (rect.0 + delta.0 * time, rect.1 + delta.1 * time);
+                    let hit = SweepHit { entity, position, normal, time };
+                    if best.is_none_or(|best| (hit.time, hit.entity) < (best.time, best.entity)) {
+                        best = Some(hit);
+                    }
+                }
+            }
+            // As for rays, a hit exactly at the end of this stretch may tie
+            // with a lower entity in the next one
+            if best.is_some_and(|hit| hit.time < to) {
+                break;
+            }
+        }
+        best
+    }
+
+    fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.cell_size).floor() as i32, (y / self.cell_size).floor() as i32)
+    }
+
+    /// Every occupied cell a rectangle covers, edges included.
+    fn cells_for(&self, rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds.unwrap_or(((0, 0), (-1, -1)));
+        let (min_x, min_y) = self.cell(rect.0, rect.1);
+        let (max_x, max_y) = self.cell(rect.0 + rect.2, rect.1 + rect.3);
+        let (min_y, max_y) = (min_y.max(low_y), max_y.min(high_y));
+        (min_x.max(low_x)..=max_x.min(high_x)).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+
+    /// Distances along the unit `direction` between which a box of `size`,
+    /// its top-left corner moving from `origin`, is over occupied cells.
+    /// `None` if it never is.
+    fn occupied_span(&self, origin: (f32, f32), direction: (f32, f32), size: (f32, f32)) -> Option<(f32, f32)> {
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds?;
+        let axis = |pos: f32, d: f32, size: f32, low: i32, high: i32| {
+            let (min, max) = (low as f32 * self.cell_size - size, (high + 1) as f32 * self.cell_size);
+            if d != 0.0 {
+                let (a, b) = ((min - pos) / d, (max - pos) / d);
+                Some((a.min(b), a.max(b)))
+            } else if pos >= min && pos <= max {
+                Some((f32::NEG_INFINITY, f32::INFINITY))
+            } else {
+                None
+            }
+        };
+        let (enter_x, leave_x) = axis(origin.0, direction.0, size.0, low_x, high_x)?;
+        let (enter_y, leave_y) = axis(origin.1, direction.1, size.1, low_y, high_y)?;
+        let (enter, leave) = (enter_x.max(enter_y).max(0.0), leave_x.min(leave_y));
+        Some((enter, leave)).filter(|(enter, leave)| enter <= leave)
+    }
+}
+
+impl Default for SpatialGrid {
+    /// Cells of 64px, a little over one default tile.
+    fn default() -> Self {
+        SpatialGrid::new(64.0)
+    }
+}
+
+/// Which way a ray along `d` crosses cells on one axis.
+fn step_towards(d: f32) -> i32 {
+    if d > 0.0 {
+        1
+    } else if d < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+fn touches(a: Rect, b: Rect) -> bool {
+    a.0 <= b.0 + b.2 && a.0 + a.2 >= b.0 && a.1 <= b.1 + b.3 && a.1 + a.3 >= b.1
+}
@@ -0,0 +1,68 @@
+use specs::{System, ReadStorage, Join, Read, Write};
+use crate::components::{Position, Velocity, Renderable, Player};
+use crate::resources::{Camera, WorldBounds};
+
+pub struct CameraSystem;
+
+impl<'a> System<'a> for CameraSystem {
+    type SystemData = (
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Player>,
+        Write<'a, Camera>,
+        Read<'a, WorldBounds>,
+        Read<'a, f32>,
+    );
+
+    fn run(&mut self, (positions, velocities, renderables, players, mut camera, bounds, delta_time): Self::SystemData) {
+        let dt = *delta_time;
+
+        let target = (&positions, &velocities, &renderables, &players).join().next();
+        let (pos, vel, render, _player) = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        // Aim at the player's center, pushed ahead in the direction of travel
+        let mut target_x = pos.x + render.width / 2.0;
+        let target_y = pos.y + render.height / 2.0;
+        if vel.x.abs() > 1.0 {
+            target_x += camera.look_ahead * vel.x.signum();
+        }
+
+        let half_w = camera.viewport_width / 2.0;
+        let half_h = camera.viewport_height / 2.0;
+
+        if camera.needs_snap() {
+            let (x, y) = camera.clamp_to(target_x - half_w, target_y - half_h, &bounds);
+            camera.move_to(x, y, true);
+            return;
+        }
+
+        // Only follow once the target leaves the dead zone around the center
+        let center_x = camera.x + half_w;
+        let center_y = camera.y + half_h;
+        let (dead_w, dead_h) = (camera.dead_zone.0 / 2.0, camera.dead_zone.1 / 2.0);
+        let desired_x = if target_x > center_x + dead_w {
+            target_x - dead_w - half_w
+        } else if target_x < center_x - dead_w {
+            target_x + dead_w - half_w
+        } else {
+            camera.x
+        };
+        let desired_y = if target_y > center_y + dead_h {
+            target_y - dead_h - half_h
+        } else if target_y < center_y - dead_h {
+            target_y + dead_h - half_h
+        } else {
+            camera.y
+        };
+
+        let follow = 1.0 - (-camera.smoothing * dt).exp();
+        let x = camera.x + (desired_x - camera.x) * follow;
+        let y = camera.y + (desired_y - camera.y) * follow;
+        let (x, y) = camera.clamp_to(x, y, &bounds);
+        camera.move_to(x, y, false);
+    }
+}
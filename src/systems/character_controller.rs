@@ -0,0 +1,67 @@
+use specs::{System, ReadStorage, WriteStorage, Join, Read, Entities};
+use crate::components::{Velocity, Player, CharacterController, Grounded, Knockback, OnMaterial, PhysicsMaterial};
+
+/// Moves each `CharacterController` body as its intent asks: accelerates
+/// towards the walking speed, and jumps when a (possibly buffered) press
+/// meets the ground or coyote time. Letting go of jump while rising cuts the
+/// jump short. Gravity, from the level's `PhysicsConfig`, and the fall speed
+/// cap are applied by `MovementSystem`.
+pub struct CharacterControllerSystem;
+
+impl<'a> System<'a> for CharacterControllerSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Velocity>,
+        WriteStorage<'a, CharacterController>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Grounded>,
+        ReadStorage<'a, Knockback>,
+        ReadStorage<'a, OnMaterial>,
+        Read<'a, f32>,
+    );
+
+    fn run(&mut self, (entities, mut velocities, mut controllers, players, grounded, knockbacks, on_materials, delta_time): Self::SystemData) {
+        let dt = *delta_time;
+
+        for (entity, vel, controller, player) in (&entities, &mut velocities, &mut controllers, &players).join() {
+            let tuning = controller.tuning;
+            let is_grounded = grounded.contains(entity);
+
+            // Knockback keeps its push until it wears off
+            if !knockbacks.contains(entity) {
+                // The ground's material changes grip and walking speed
+                let material = match (is_grounded, on_materials.get(entity)) {
+                    (true, Some(on)) => on.material,
+                    _ => PhysicsMaterial::default(),
+                };
+                let target = controller.move_x.clamp(-1.0, 1.0) * player.speed * material.speed;
+                let acceleration = if is_grounded { tuning.ground_acceleration * material.friction } else { tuning.air_acceleration };
+                let step = acceleration * dt;
+                vel.x = if (target - vel.x).abs() <= step { target } else { vel.x + step * (target - vel.x).signum() };
+            }
+
+            if is_grounded {
+                controller.coyote = tuning.coyote_time;
+            } else {
+                controller.coyote -= dt;
+            }
+            if controller.jump_pressed {
+                controller.buffered = tuning.jump_buffer;
+            } else {
+                controller.buffered -= dt;
+            }
+
+            if controller.buffered > 0.0 && controller.coyote > 0.0 {
+                vel.y = -player.jump_force;
+                controller.buffered = 0.0;
+                controller.coyote = 0.0;
+                controller.rising = true;
+            } else if controller.rising && (vel.y >= 0.0 || !controller.jump_held) {
+                if vel.y < 0.0 {
+                    vel.y *= tuning.jump_cut;
+                }
+                controller.rising = false;
+            }
+        }
+    }
+}
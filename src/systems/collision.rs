@@ -1,119 +1,340 @@
-use specs::{System, ReadStorage, WriteStorage, Join, Entities};
-use crate::components::{Position, Collidable, Collectible, Platform, Velocity, Grounded, Player, Enemy, Renderable, Health};
+use std::collections::BTreeSet;
 
-pub struct CollisionSystem;
+use specs::shrev::EventChannel;
+use specs::{System, ReadStorage, WriteStorage, Join, Entities, Entity, Read, Write, BitSet};
+use crate::components::{Position, PreviousPosition, Collider, ColliderShape, Velocity, Grounded, Renderable, OneWay, DropThrough, Slope, OnSlope, PhysicsMaterial, OnMaterial};
+use crate::events::{CollisionEvent, Contact};
+use crate::geometry::{check_aabb, contact, shape_contact, sweep_aabb, Rect, Shape};
+use crate::spatial::SpatialGrid;
+
+/// Something a moving body is stopped by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Solid {
+    rect: Rect,
+    /// Only blocks bodies landing on its top.
+    one_way: bool,
+    material: Option<PhysicsMaterial>,
+}
+
+impl Solid {
+    fn bounciness(&self) -> f32 {
+        self.material.map_or(0.0, |material| material.bounciness)
+    }
+}
+
+/// Most surfaces a body can hit in one step, e.g. a floor then a wall.
+const MAX_SWEEPS: usize = 3;
+
+/// How close a body's bottom must be to a platform's top to stand on it.
+const GROUND_EPSILON: f32 = 0.01;
+
+/// Extra distance a grounded body is pulled down to stay on a slope, on top
+/// of the drop due to its horizontal movement.
+const SLOPE_SNAP: f32 = 2.0;
+
+/// Slowest bounce off a bouncy surface; slower ones stop dead instead, so
+/// bodies come to rest rather than bouncing forever.
+const MIN_BOUNCE_SPEED: f32 = 60.0;
+
+/// Resolves moving bodies against what blocks them and publishes a
+/// `CollisionEvent` whenever one starts or stops touching a body it
+/// triggers on. What blocks and triggers what comes from each body's
+/// `CollisionFilter`, stored in the `SpatialGrid`.
+///
+/// Bodies collide with their `Collider`, or their `Renderable` rectangle
+/// without one. Contacts use the exact shapes; blocking is resolved
+/// against the bounding box of each shape. A body touching something that
+/// blocks it is in contact, while trigger-only pairs must overlap.
+///
+/// Resolution is continuous: each body is swept from its `PreviousPosition`
+/// to where `MovementSystem` left it, stopping at the first blocking body in
+/// the way, so fast bodies cannot pass through thin platforms. `OneWay`
+/// platforms only stop bodies coming down onto them, and not at all while
+/// the body has `DropThrough`. Bodies stand on the highest point of any
+/// `Slope` under them and are kept on it while walking down. Bodies bounce
+/// off surfaces with a bouncy `PhysicsMaterial`, and stand on a surface's
+/// material through `OnMaterial`. Nearby bodies are found through the
+/// `SpatialGrid`, which must be up to date.
+#[derive(Default)]
+pub struct CollisionSystem {
+    /// Pairs touching at the end of the previous step, `(moving, other)`.
+    contacts: BTreeSet<(Entity, Entity)>,
+}
 
 impl<'a> System<'a> for CollisionSystem {
     type SystemData = (
         Entities<'a>,
-        WriteStorage<'a, Position>, 
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, PreviousPosition>,
         ReadStorage<'a, Renderable>,
-        ReadStorage<'a, Collidable>,
-        ReadStorage<'a, Collectible>,
-        ReadStorage<'a, Platform>,
-        ReadStorage<'a, Enemy>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, OneWay>,
+        ReadStorage<'a, DropThrough>,
+        ReadStorage<'a, Slope>,
+        ReadStorage<'a, PhysicsMaterial>,
         WriteStorage<'a, Velocity>,
         WriteStorage<'a, Grounded>,
-        WriteStorage<'a, Player>,
-        WriteStorage<'a, Health>,
+        WriteStorage<'a, OnSlope>,
+        WriteStorage<'a, OnMaterial>,
+        Read<'a, SpatialGrid>,
+        Write<'a, EventChannel<CollisionEvent>>,
     );
 
-    fn run(&mut self, (entities, mut positions, renderables, _collidables, collectibles, platforms, enemies, mut velocities, mut grounded, mut players, _healths): Self::SystemData) {
+    fn run(&mut self, (entities, mut positions, previous_positions, renderables, colliders, one_ways, drop_throughs, slopes, materials, mut velocities, mut grounded, mut on_slopes, mut on_materials, grid, mut events): Self::SystemData) {
+        let was_grounded = grounded.mask().clone();
         grounded.clear();
+        on_slopes.clear();
+        on_materials.clear();
 
-        // We need to collect collisions first to avoid double borrow issues if we were to try complex things,
-        // but actually, if we iterate over entities and then query others, we might be okay if we don't borrow `positions` for the whole loop?
-        // No, `join` borrows the storage.
-        
-        // 1. Platform Collision & Resolution
-        let mut resolutions = Vec::new();
-        for (entity, pos, vel, render, _player) in (&entities, &positions, &velocities, &renderables, &players).join() {
-            let player_rect = (pos.x, pos.y, render.width, render.height);
-
-            for (platform_pos, platform_render, _platform) in (&positions, &renderables, &platforms).join() {
-                let platform_rect = (platform_pos.x, platform_pos.y, platform_render.width, platform_render.height);
-
-                if check_aabb(player_rect, platform_rect) {
-                    let mut new_pos = (pos.x, pos.y);
-                    let mut new_vel = (vel.x, vel.y);
-                    let mut on_ground = false;
-
-                    // Vertical Resolution (Top/Bottom)
-                    if vel.y > 0.0 && pos.y + render.height <= platform_pos.y + 15.0 {
-                        new_pos.1 = platform_pos.y - render.height;
-                        new_vel.1 = 0.0;
-                        on_ground = true;
-                    } else if vel.y < 0.0 && pos.y >= platform_pos.y + platform_render.height - 15.0 {
-                        new_pos.1 = platform_pos.y + platform_render.height;
-                        new_vel.1 = 0.0;
-                    }
-                    // Horizontal Resolution (Sides) - Simplified
-                    else if vel.x > 0.0 && pos.x + render.width <= platform_pos.x + 10.0 {
-                        new_pos.0 = platform_pos.x - render.width;
-                        new_vel.0 = 0.0;
-                    } else if vel.x < 0.0 && pos.x >= platform_pos.x + platform_render.width - 10.0 {
-                        new_pos.0 = platform_pos.x + platform_render.width;
-                        new_vel.0 = 0.0;
+        // Moving bodies that block or trigger on anything
+        let mut movers = BitSet::new();
+        for (entity, _vel) in (&entities, &velocities).join() {
+            if grid.filter(entity).is_some_and(|filter| !(filter.blocks | filter.triggers).is_empty()) {
+                movers.add(entity.id());
+            }
+        }
+
+        // 0. Contact events, from positions before any resolution
+        let shape_of = |entity| body_shape(positions.get(entity)?, colliders.get(entity), renderables.get(entity));
+        let mut touching = BTreeSet::new();
+        for (a, _) in (&entities, &movers).join() {
+            let (a_shape, a_filter) = match (shape_of(a), grid.filter(a)) {
+                (Some(shape), Some(filter)) => (shape, filter),
+                _ => continue,
+            };
+            for b in grid.query_aabb(a_shape.bounds()) {
+                // Moving pairs are reported once, with the lower entity first
+                let b_moving = movers.contains(b.id());
+                if a == b || (b_moving && b < a) {
+                    continue;
+                }
+                let b_filter = match grid.filter(b) {
+                    Some(filter) => filter,
+                    None => continue,
+                };
+                if !(a_filter.triggers.intersects(b_filter.layers) || b_filter.triggers.intersects(a_filter.layers)) {
+                    continue;
+                }
+                // Resting against something solid counts as touching it, but
+                // a trigger such as a coin has to be overlapped
+                let blocking = a_filter.blocks.intersects(b_filter.layers) || b_filter.blocks.intersects(a_filter.layers);
+                let contact = shape_of(b)
+                    .and_then(|b_shape| shape_contact(a_shape, b_shape))
+                    .filter(|(_, penetration)| blocking || *penetration > 0.0);
+                if let Some((normal, penetration)) = contact {
+                    touching.insert((a, b));
+                    if !self.contacts.contains(&(a, b)) {
+                        events.single_write(CollisionEvent::Started(Contact { a, b, normal, penetration }));
                     }
-                    resolutions.push((entity, new_pos, new_vel, on_ground));
                 }
             }
         }
+        for &(a, b) in self.contacts.difference(&touching) {
+            events.single_write(CollisionEvent::Ended { a, b });
+        }
+        self.contacts = touching;
 
-        for (entity, new_pos, new_vel, on_ground) in resolutions {
-            if let Some(pos) = positions.get_mut(entity) {
-                pos.x = new_pos.0;
-                pos.y = new_pos.1;
-            }
-            if let Some(vel) = velocities.get_mut(entity) {
-                vel.x = new_vel.0;
-                vel.y = new_vel.1;
+        // 1. Blocking, against the bodies around each mover's path
+        for (entity, pos, vel) in (&entities, &mut positions, &mut velocities).join() {
+            let blocks = match grid.filter(entity) {
+                Some(filter) if !filter.blocks.is_empty() => filter.blocks,
+                _ => continue,
+            };
+            let size = match body_shape(pos, colliders.get(entity), renderables.get(entity)) {
+                Some(shape) => (shape.bounds().2, shape.bounds().3),
+                None => continue,
+            };
+            // Sweep the bounding box, then move the position by as much
+            let offset = colliders.get(entity).map_or((0.0, 0.0), |collider| collider.offset);
+            let previous = previous_positions.get(entity).map_or((pos.x, pos.y), |prev| (prev.x, prev.y));
+            let start = (previous.0 + offset.0, previous.1 + offset.1);
+            let delta = (pos.x - previous.0, pos.y - previous.1);
+            // Reaching down far enough to find slopes to stay glued to
+            let snap = delta.0.abs() + SLOPE_SNAP;
+            let path = (
+                start.0.min(start.0 + delta.0),
+                start.1.min(start.1 + delta.1),
+                delta.0.abs() + size.0,
+                delta.1.abs() + size.1 + snap,
+            );
+            let dropping = drop_throughs.contains(entity);
+            let mut solids: Vec<Solid> = Vec::new();
+            let mut ramps: Vec<(Rect, Slope, Option<PhysicsMaterial>)> = Vec::new();
+            for solid in grid.query_layers(path, blocks) {
+                let rect = match grid.rect(solid) {
+                    Some(rect) if solid != entity && !(dropping && one_ways.contains(solid)) => rect,
+                    _ => continue,
+                };
+                let material = materials.get(solid).copied();
+                match slopes.get(solid) {
+                    Some(slope) => ramps.push((rect, *slope, material)),
+                    None => solids.push(Solid { rect, one_way: one_ways.contains(solid), material }),
+                }
             }
-            if on_ground {
+            let (mut end, ground) = sweep_body(start, delta, size, &solids, vel);
+            // Only bodies that were standing and have nothing flat under
+            // them now are pulled down onto a slope
+            let snap = if was_grounded.contains(entity.id()) && ground.is_none() { snap } else { 0.0 };
+            let on_slope = settle_on_slopes(start, &mut end, size, &ramps, vel, snap);
+            // Stepping off a slope onto flat ground slightly below
+            let ground = match on_slope {
+                Some(index) => Some(ramps[index].2),
+                None => ground.or_else(|| snap_to_ground(&mut end, size, &solids, vel, snap)).map(|solid| solid.material),
+            };
+            pos.x = end.0 - offset.0;
+            pos.y = end.1 - offset.1;
+            if let Some(material) = ground {
                 grounded.insert(entity, Grounded).ok();
+                if let Some(material) = material {
+                    on_materials.insert(entity, OnMaterial { material }).ok();
+                }
+            }
+            if let Some(index) = on_slope {
+                let (rect, slope, _) = ramps[index];
+                on_slopes.insert(entity, OnSlope { gradient: slope.gradient(rect) }).ok();
             }
         }
+    }
+}
 
-        // 2. Enemy Collisions (Damage)
-        let mut damaged_players = Vec::new();
-        for (player_entity, player_pos, player_render, _player) in (&entities, &positions, &renderables, &players).join() {
-            let player_rect = (player_pos.x, player_pos.y, player_render.width, player_render.height);
-            for (enemy_pos, enemy_render, _enemy) in (&positions, &renderables, &enemies).join() {
-                let enemy_rect = (enemy_pos.x, enemy_pos.y, enemy_render.width, enemy_render.height);
-                if check_aabb(player_rect, enemy_rect) {
-                    damaged_players.push(player_entity);
-                }
-            }
+/// Moves a body of `size` from `start` by `delta`, stopping at `solids` and
+/// sliding along them. Velocity into a surface that was hit is removed, or
+/// reversed if the surface is bouncy. Returns the final position and the
+/// solid the body is standing on, if any.
+fn sweep_body(start: (f32, f32), delta: (f32, f32), size: (f32, f32), solids: &[Solid], vel: &mut Velocity) -> ((f32, f32), Option<Solid>) {
+    let mut pos = start;
+    let mut remaining = delta;
+
+    for _ in 0..MAX_SWEEPS {
+        if remaining == (0.0, 0.0) {
+            break;
         }
+        let body = (pos.0, pos.1, size.0, size.1);
+        // Earliest hit; on a tie, landing on a top wins over a side
+        let hit = solids.iter()
+            .filter_map(|solid| sweep_aabb(body, remaining, solid.rect).map(|(t, normal)| (t, normal, solid)))
+            .filter(|(_, normal, solid)| !solid.one_way || *normal == (0.0, -1.0))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then((a.1.0 != 0.0).cmp(&(b.1.0 != 0.0))));
 
-        // 3. Collectibles
-        let mut to_remove = Vec::new();
-        let mut score_updates = Vec::new();
-        for (player_entity, player_pos, player_render, _player) in (&entities, &positions, &renderables, &players).join() {
-            let player_rect = (player_pos.x, player_pos.y, player_render.width, player_render.height);
-            for (collectible_entity, collectible_pos, collectible_render, _collectible) in (&entities, &positions, &renderables, &collectibles).join() {
-                let collectible_rect = (collectible_pos.x, collectible_pos.y, collectible_render.width, collectible_render.height);
-                if check_aabb(player_rect, collectible_rect) {
-                    to_remove.push(collectible_entity);
-                    score_updates.push((player_entity, 10));
-                }
+        let (t, normal, solid) = match hit {
+            Some(hit) => hit,
+            None => {
+                pos = (pos.0 + remaining.0, pos.1 + remaining.1);
+                break;
+            },
+        };
+        let bounce = |speed: f32| {
+            let back = -speed * solid.bounciness();
+            if back.abs() >= MIN_BOUNCE_SPEED { back } else { 0.0 }
+        };
+        let rect = solid.rect;
+        pos = (pos.0 + remaining.0 * t, pos.1 + remaining.1 * t);
+        // Snap flush to the surface so rounding cannot leave the body inside
+        if normal.0 != 0.0 {
+            pos.0 = if normal.0 < 0.0 { rect.0 - size.0 } else { rect.0 + rect.2 };
+            remaining = (0.0, remaining.1 * (1.0 - t));
+            if vel.x * normal.0 < 0.0 {
+                vel.x = bounce(vel.x);
+            }
+        } else {
+            pos.1 = if normal.1 < 0.0 { rect.1 - size.1 } else { rect.1 + rect.3 };
+            remaining = (remaining.0 * (1.0 - t), 0.0);
+            if vel.y * normal.1 < 0.0 {
+                vel.y = bounce(vel.y);
             }
         }
+    }
 
-        for e in to_remove {
-            entities.delete(e).unwrap();
+    // Push out of anything the body started inside, e.g. at spawn. Bodies
+    // part-way through a one-way platform are left to finish passing it.
+    for solid in solids.iter().filter(|solid| !solid.one_way).map(|solid| solid.rect) {
+        let body = (pos.0, pos.1, size.0, size.1);
+        if !check_aabb(body, solid) {
+            continue;
         }
-        for (entity, score) in score_updates {
-            if let Some(p) = players.get_mut(entity) {
-                p.score += score;
+        if let Some((normal, penetration)) = contact(body, solid) {
+            pos = (pos.0 + normal.0 * penetration, pos.1 + normal.1 * penetration);
+            if vel.x * normal.0 < 0.0 {
+                vel.x = 0.0;
+            }
+            if vel.y * normal.1 < 0.0 {
+                vel.y = 0.0;
             }
         }
     }
+
+    // Standing on whichever solid under the feet holds up most of the body
+    let bottom = pos.1 + size.1;
+    let overlap = |rect: Rect| (pos.0 + size.0).min(rect.0 + rect.2) - pos.0.max(rect.0);
+    let ground = solids.iter()
+        .filter(|solid| vel.y >= 0.0 && (solid.rect.1 - bottom).abs() < GROUND_EPSILON && overlap(solid.rect) > 0.0)
+        .max_by(|a, b| overlap(a.rect).total_cmp(&overlap(b.rect)))
+        .copied();
+    (pos, ground)
 }
 
-fn check_aabb(r1: (f32, f32, f32, f32), r2: (f32, f32, f32, f32)) -> bool {
-    r1.0 < r2.0 + r2.2 &&
-    r1.0 + r1.2 > r2.0 &&
-    r1.1 < r2.1 + r2.3 &&
-    r1.1 + r1.3 > r2.1
-}
\ No newline at end of file
+/// Where an entity collides: its `Collider` placed at `pos`, or else its
+/// `Renderable` rectangle. `None` if it has neither.
+pub fn body_shape(pos: &Position, collider: Option<&Collider>, render: Option<&Renderable>) -> Option<Shape> {
+    match (collider, render) {
+        (Some(collider), _) => {
+            let (x, y) = (pos.x + collider.offset.0, pos.y + collider.offset.1);
+            Some(match collider.shape {
+                ColliderShape::Aabb { width, height } => Shape::Rect((x, y, width, height)),
+                ColliderShape::Circle { radius } => Shape::Circle { x: x + radius, y: y + radius, radius },
+            })
+        },
+        (None, Some(render)) => Some(Shape::Rect((pos.x, pos.y, render.width, render.height))),
+        (None, None) => None,
+    }
+}
+
+/// Stands a body that moved from `start` to `pos` on the highest slope
+/// surface under it, if it reached or passed through that surface this
+/// step, or is within `snap` above it. Returns the index in `ramps` of the
+/// slope it ends up standing on.
+fn settle_on_slopes(start: (f32, f32), pos: &mut (f32, f32), size: (f32, f32), ramps: &[(Rect, Slope, Option<PhysicsMaterial>)], vel: &mut Velocity, snap: f32) -> Option<usize> {
+    let (left, right) = (pos.0, pos.0 + size.0);
+    let (index, surface, gradient) = ramps.iter()
+        .enumerate()
+        .filter(|(_, (rect, _, _))| left < rect.0 + rect.2 && right > rect.0)
+        .map(|(index, (rect, slope, _))| {
+            // The surface is straight, so its highest point is at one end
+            let (from, to) = (left.max(rect.0), right.min(rect.0 + rect.2));
+            (index, slope.surface_y(*rect, from).min(slope.surface_y(*rect, to)), slope.gradient(*rect))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let bottom = pos.1 + size.1;
+    // How far the surface can have risen under the body since `start`
+    let climb = (pos.0 - start.0).abs() * gradient.abs() + GROUND_EPSILON;
+    let landed = bottom >= surface - GROUND_EPSILON && start.1 + size.1 <= surface + climb;
+    let glued = vel.y >= 0.0 && bottom < surface && surface - bottom <= snap;
+    if !(landed || glued) {
+        return None;
+    }
+    pos.1 = surface - size.1;
+    if vel.y > 0.0 {
+        vel.y = 0.0;
+    }
+    Some(index)
+}
+
+/// Moves a body at `pos` down onto the top of a solid at most `snap` below
+/// it. Returns the solid it now stands on.
+fn snap_to_ground(pos: &mut (f32, f32), size: (f32, f32), solids: &[Solid], vel: &mut Velocity, snap: f32) -> Option<Solid> {
+    let bottom = pos.1 + size.1;
+    let ground = solids.iter()
+        .filter(|solid| pos.0 < solid.rect.0 + solid.rect.2 && pos.0 + size.0 > solid.rect.0)
+        .filter(|solid| solid.rect.1 >= bottom && solid.rect.1 - bottom <= snap)
+        .min_by(|a, b| a.rect.1.total_cmp(&b.rect.1))
+        .copied();
+    match ground {
+        Some(solid) if vel.y >= 0.0 => {
+            pos.1 = solid.rect.1 - size.1;
+            vel.y = 0.0;
+            Some(solid)
+        },
+        _ => None,
+    }
+}
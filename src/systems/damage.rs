@@ -0,0 +1,100 @@
+use std::collections::BTreeSet;
+
+use specs::shrev::{EventChannel, ReaderId};
+use specs::{System, SystemData, ReadStorage, WriteStorage, Read, Entities, Entity, World};
+use crate::components::{Position, Renderable, Collider, Velocity, Player, Enemy, Health, Damage, Invulnerable, Knockback, Dead};
+use crate::events::CollisionEvent;
+use crate::resources::CombatSettings;
+use crate::geometry::shapes_overlap;
+use crate::systems::collision::body_shape;
+
+/// Hurts players overlapping enemies, then grants invulnerability and
+/// knockback. Contacts come from the collision event channel. Players whose
+/// health runs out are marked `Dead` by `HealthSystem`.
+#[derive(Default)]
+pub struct DamageSystem {
+    reader: Option<ReaderId<CollisionEvent>>,
+    /// `(player, enemy)` pairs currently touching.
+    touching: BTreeSet<(Entity, Entity)>,
+}
+
+impl<'a> System<'a> for DamageSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Enemy>,
+        ReadStorage<'a, Damage>,
+        WriteStorage<'a, Velocity>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Invulnerable>,
+        WriteStorage<'a, Knockback>,
+        ReadStorage<'a, Dead>,
+        Read<'a, CombatSettings>,
+        Read<'a, EventChannel<CollisionEvent>>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(world.fetch_mut::<EventChannel<CollisionEvent>>().register_reader());
+    }
+
+    fn run(&mut self, (entities, positions, renderables, colliders, players, enemies, damages, mut velocities, mut healths, mut invulnerables, mut knockbacks, dead, combat, events): Self::SystemData) {
+        let reader = self.reader.as_mut().expect("DamageSystem::setup was not called");
+        for event in events.read(reader) {
+            let (a, b) = event.entities();
+            let pair = if players.contains(a) && enemies.contains(b) {
+                (a, b)
+            } else if players.contains(b) && enemies.contains(a) {
+                (b, a)
+            } else {
+                continue;
+            };
+            match event {
+                CollisionEvent::Started(_) => self.touching.insert(pair),
+                CollisionEvent::Ended { .. } => self.touching.remove(&pair),
+            };
+        }
+
+        // At most one hit per player per step, from the first enemy overlapped.
+        // Touching edges is not enough to get hurt.
+        let mut damaged_players: Vec<(Entity, i32, f32)> = Vec::new();
+        for &(player, enemy) in &self.touching {
+            if invulnerables.contains(player) || dead.contains(player) || damaged_players.iter().any(|(e, _, _)| *e == player) {
+                continue;
+            }
+            let shape = |entity| body_shape(positions.get(entity)?, colliders.get(entity), renderables.get(entity));
+            let (player_shape, enemy_shape) = match (shape(player), shape(enemy)) {
+                (Some(p), Some(e)) => (p, e),
+                _ => continue,
+            };
+            if shapes_overlap(player_shape, enemy_shape) {
+                let amount = damages.get(enemy).map_or(combat.contact_damage, |damage| damage.amount);
+                // Knock the player away from the enemy's center
+                let (p, e) = (player_shape.bounds(), enemy_shape.bounds());
+                let away = if p.0 + p.2 / 2.0 < e.0 + e.2 / 2.0 { -1.0 } else { 1.0 };
+                damaged_players.push((player, amount, away));
+            }
+        }
+
+        for (entity, amount, away) in damaged_players {
+            if let Some(health) = healths.get_mut(entity) {
+                health.current = (health.current - amount).max(0);
+                if health.current == 0 {
+                    continue;
+                }
+            }
+            if let Some(vel) = velocities.get_mut(entity) {
+                vel.x = away * combat.knockback.0;
+                vel.y = -combat.knockback.1;
+            }
+            invulnerables.insert(entity, Invulnerable { remaining: combat.invulnerability }).ok();
+            knockbacks.insert(entity, Knockback { remaining: combat.knockback_time }).ok();
+        }
+
+        // Forget pairs whose entities were deleted without an `Ended` yet
+        self.touching.retain(|&(player, enemy)| entities.is_alive(player) && entities.is_alive(enemy));
+    }
+}
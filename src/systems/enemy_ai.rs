@@ -1,6 +1,23 @@
-use specs::{System, WriteStorage, ReadStorage, Join, Read, Entities};
-use crate::components::{Position, Velocity, Enemy, Platform, Renderable};
+use specs::{System, WriteStorage, ReadStorage, Join, Read, Entities, Entity};
+use crate::components::{Position, Velocity, Enemy, Renderable, Collider, Layers, Slope, Dead};
+use crate::resources::WorldBounds;
+use crate::spatial::SpatialGrid;
+use crate::systems::collision::body_shape;
 
+/// How many steps of movement ahead enemies look for walls and edges.
+const LOOK_AHEAD_STEPS: f32 = 5.0;
+
+/// Deepest drop an enemy walks off rather than turning back.
+const MAX_DROP: f32 = 10.0;
+
+/// How far, centre to centre, an enemy can see a player.
+const SIGHT_RANGE: f32 = 200.0;
+
+/// Walks enemies back and forth, turning at walls, at edges and at the sides
+/// of the level. Walls are found by sweeping the enemy's body ahead through
+/// the `SpatialGrid`, and edges by casting down in front of its feet.
+/// Enemies turn to face the nearest living player within `SIGHT_RANGE` that
+/// they have a clear line of sight to, unless a wall or edge is that way.
 pub struct EnemyAISystem;
 
 impl<'a> System<'a> for EnemyAISystem {
@@ -9,34 +26,64 @@ impl<'a> System<'a> for EnemyAISystem {
         WriteStorage<'a, Velocity>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Enemy>,
-        ReadStorage<'a, Platform>,
         ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Slope>,
+        ReadStorage<'a, Dead>,
         Read<'a, f32>,
+        Read<'a, WorldBounds>,
+        Read<'a, SpatialGrid>,
     );
 
-    fn run(&mut self, (_entities, mut velocities, positions, enemies, platforms, renderables, delta_time): Self::SystemData) {
+    fn run(&mut self, (entities, mut velocities, positions, enemies, renderables, colliders, slopes, dead, delta_time, bounds, grid): Self::SystemData) {
         let dt = *delta_time;
 
-        for (_enemy, vel, pos, render) in (&enemies, &mut velocities, &positions, &renderables).join() {
+        for (entity, _enemy, vel, pos) in (&entities, &enemies, &mut velocities, &positions).join() {
+            let body = match body_shape(pos, colliders.get(entity), renderables.get(entity)) {
+                Some(shape) => shape.bounds(),
+                None => continue,
+            };
             // Simple Patrol: Move back and forth, check for "edges" or "walls"
-            if pos.x <= 0.0 || pos.x >= 760.0 {
+            if body.0 <= bounds.min_x || body.0 + body.2 >= bounds.max_x {
                 vel.x = -vel.x;
-            } else {
-                // Edge Detection: Check if there's a platform below the next position
-                let next_x = pos.x + vel.x * dt * 5.0; // Check a bit ahead
-                let mut has_ground_ahead = false;
-                for (plat_pos, plat_render, _plat) in (&positions, &renderables, &platforms).join() {
-                    if next_x + render.width > plat_pos.x && next_x < plat_pos.x + plat_render.width {
-                        if (plat_pos.y - (pos.y + render.height)).abs() < 10.0 {
-                            has_ground_ahead = true;
-                            break;
-                        }
-                    }
+            } else if vel.x != 0.0 {
+                // Anything the enemy cannot walk through is a wall or ground
+                let solid = grid.filter(entity).map_or(Layers::PLATFORM, |filter| filter.blocks);
+
+                // Face a player in sight, then check the way ahead in that direction
+                let eye = (body.0 + body.2 / 2.0, body.1 + body.3 / 2.0);
+                let seen = visible_player(&grid, eye, solid, |player| !dead.contains(player));
+                if seen.is_some_and(|player| (player.0 - eye.0) * vel.x < 0.0) {
+                    vel.x = -vel.x;
                 }
-                if !has_ground_ahead {
+
+                let direction = vel.x.signum();
+                let ahead = vel.x.abs() * dt * LOOK_AHEAD_STEPS;
+                let front = if direction > 0.0 { body.0 + body.2 } else { body.0 };
+
+                // Slopes can be walked up, so only count as ground
+                let wall = grid.sweep(body, (direction * ahead, 0.0), solid)
+                    .is_some_and(|hit| !slopes.contains(hit.entity));
+                let x = front + direction * ahead;
+                let ground_ahead = grid.segment_cast((x, body.1), (x, body.1 + body.3 + MAX_DROP), solid).is_some();
+                if wall || !ground_ahead {
                     vel.x = -vel.x;
                 }
             }
         }
     }
 }
+
+/// Centre of the nearest player within `SIGHT_RANGE` of `eye` for which
+/// `alive` holds and nothing on `solid` is in the way.
+fn visible_player<F: Fn(Entity) -> bool>(grid: &SpatialGrid, eye: (f32, f32), solid: Layers, alive: F) -> Option<(f32, f32)> {
+    let area = (eye.0 - SIGHT_RANGE, eye.1 - SIGHT_RANGE, SIGHT_RANGE * 2.0, SIGHT_RANGE * 2.0);
+    grid.query_layers(area, Layers::PLAYER).into_iter()
+        .filter(|player| alive(*player))
+        .filter_map(|player| grid.rect(player))
+        .map(|rect| (rect.0 + rect.2 / 2.0, rect.1 + rect.3 / 2.0))
+        .map(|centre| (centre, (centre.0 - eye.0).hypot(centre.1 - eye.1)))
+        .filter(|&(centre, distance)| distance <= SIGHT_RANGE && grid.line_of_sight(eye, centre, solid))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(centre, _)| centre)
+}
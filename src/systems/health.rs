@@ -0,0 +1,51 @@
+use specs::{System, ReadStorage, WriteStorage, Join, Read, Entities};
+use crate::components::{Health, Invulnerable, Knockback, Dead};
+
+/// Counts down hit timers and marks anything whose health has run out as
+/// `Dead`. The only system that inserts `Dead`.
+pub struct HealthSystem;
+
+impl<'a> System<'a> for HealthSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Health>,
+        WriteStorage<'a, Invulnerable>,
+        WriteStorage<'a, Knockback>,
+        WriteStorage<'a, Dead>,
+        Read<'a, f32>,
+    );
+
+    fn run(&mut self, (entities, healths, mut invulnerables, mut knockbacks, mut dead, delta_time): Self::SystemData) {
+        let dt = *delta_time;
+
+        // Count down hit timers
+        let mut expired = Vec::new();
+        for (entity, invulnerable) in (&entities, &mut invulnerables).join() {
+            invulnerable.remaining -= dt;
+            if invulnerable.remaining <= 0.0 {
+                expired.push(entity);
+            }
+        }
+        for entity in expired {
+            invulnerables.remove(entity);
+        }
+
+        let mut recovered = Vec::new();
+        for (entity, knockback) in (&entities, &mut knockbacks).join() {
+            knockback.remaining -= dt;
+            if knockback.remaining <= 0.0 {
+                recovered.push(entity);
+            }
+        }
+        for entity in recovered {
+            knockbacks.remove(entity);
+        }
+
+        // Anything that drained health this step
+        for (entity, health) in (&entities, &healths).join() {
+            if health.current <= 0 {
+                dead.insert(entity, Dead).ok();
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use specs::{System, WriteStorage, Join};
+use specs::{System, WriteStorage, Join, Read};
 use crate::components::{Position, Player, Velocity};
+use crate::resources::{PhysicsConfig, WorldBounds};
+use crate::utils::level_loader::LevelInfo;
 
 pub struct LogicSystem;
 
@@ -8,15 +10,20 @@ impl<'a> System<'a> for LogicSystem {
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
         WriteStorage<'a, Player>,
+        Read<'a, WorldBounds>,
+        Read<'a, PhysicsConfig>,
+        Read<'a, LevelInfo>,
     );
 
-    fn run(&mut self, (mut positions, mut velocities, mut players): Self::SystemData) {
+    fn run(&mut self, (mut positions, mut velocities, mut players, bounds, physics, info): Self::SystemData) {
+        let kill_y = physics.kill_y(&bounds);
+        let spawn = info.spawn.unwrap_or((bounds.min_x, bounds.min_y));
         for (pos, vel, player) in (&mut positions, &mut velocities, &mut players).join() {
             // Check Fall (Death)
-            if pos.y > 700.0 {
+            if pos.y > kill_y {
                 // Reset
-                pos.x = 100.0;
-                pos.y = 100.0;
+                pos.x = spawn.0;
+                pos.y = spawn.1;
                 vel.x = 0.0;
                 vel.y = 0.0;
                 player.score = 0;
@@ -3,9 +3,25 @@ pub mod collision;
 pub mod particle;
 pub mod logic;
 pub mod enemy_ai;
+pub mod player_control;
+pub mod camera;
+pub mod health;
+pub mod score;
+pub mod damage;
+pub mod spatial_index;
+pub mod moving_platform;
+pub mod character_controller;
 
-pub use self::collision::CollisionSystem;
+pub use self::collision::{CollisionSystem, body_shape};
 pub use self::particle::ParticleSystem;
 pub use self::movement::MovementSystem;
 pub use self::logic::LogicSystem;
-pub use self::enemy_ai::EnemyAISystem;
\ No newline at end of file
+pub use self::enemy_ai::EnemyAISystem;
+pub use self::player_control::PlayerControlSystem;
+pub use self::camera::CameraSystem;
+pub use self::health::HealthSystem;
+pub use self::score::ScoreSystem;
+pub use self::damage::DamageSystem;
+pub use self::spatial_index::SpatialIndexSystem;
+pub use self::moving_platform::MovingPlatformSystem;
+pub use self::character_controller::CharacterControllerSystem;
\ No newline at end of file
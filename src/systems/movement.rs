@@ -1,5 +1,7 @@
 use specs::{System, ReadStorage, WriteStorage, Join, Read, Entities};
-use crate::components::{Position, Velocity, Gravity, Grounded};
+use crate::components::{Position, Velocity, Gravity, Grounded, Renderable, Collider, OnSlope, OnMaterial, CharacterController};
+use crate::resources::{PhysicsConfig, WorldBounds};
+use crate::systems::collision::body_shape;
 
 pub struct MovementSystem;
 
@@ -10,35 +12,64 @@ impl<'a> System<'a> for MovementSystem {
         WriteStorage<'a, Velocity>,
         ReadStorage<'a, Gravity>,
         ReadStorage<'a, Grounded>,
+        ReadStorage<'a, OnSlope>,
+        ReadStorage<'a, OnMaterial>,
+        ReadStorage<'a, CharacterController>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Collider>,
         Read<'a, f32>,
+        Read<'a, WorldBounds>,
+        Read<'a, PhysicsConfig>,
     );
 
-    fn run(&mut self, (entities, mut positions, mut velocities, gravity, grounded, delta_time): Self::SystemData) {
+    fn run(&mut self, (entities, mut positions, mut velocities, gravity, grounded, on_slopes, on_materials, controllers, renderables, colliders, delta_time, bounds, physics): Self::SystemData) {
         let dt = *delta_time;
         
-        for (entity, vel, _grav) in (&entities, &mut velocities, &gravity).join() {
+        for (entity, vel, grav) in (&entities, &mut velocities, &gravity).join() {
             let is_grounded = grounded.get(entity).is_some();
+            let controller = controllers.get(entity).map(|c| c.tuning);
             
             // Apply Gravity
             if !is_grounded {
-                vel.y += 1500.0 * dt; // Gravity constant
+                vel.x += physics.gravity.0 * grav.scale * dt;
+                vel.y += physics.gravity.1 * grav.scale * dt;
+                if let Some(tuning) = controller {
+                    vel.y = vel.y.min(tuning.max_fall_speed);
+                }
             } else if vel.y > 0.0 {
                 vel.y = 0.0;
             }
 
-            // Apply friction/drag on X axis
-            let friction: f32 = if is_grounded { 0.85 } else { 0.95 };
-            vel.x *= friction.powf(dt * 60.0); 
-            if vel.x.abs() < 5.0 { vel.x = 0.0; }
+            // Controlled bodies brake through their own acceleration
+            if controller.is_some() {
+                continue;
+            }
+
+            // Apply friction/drag on X axis, scaled by the ground's grip
+            let (drag, friction) = match is_grounded {
+                true => (physics.ground_drag, on_materials.get(entity).map_or(1.0, |on| on.material.friction)),
+                false => (physics.air_drag, 1.0),
+            };
+            vel.x *= drag.powf(dt * 60.0 * friction);
+            if vel.x.abs() < physics.rest_speed { vel.x = 0.0; }
         }
 
         // Apply Velocity to Position
-        for (pos, vel) in (&mut positions, &mut velocities).join() {
-            pos.x += vel.x * dt;
+        for (entity, pos, vel) in (&entities, &mut positions, &mut velocities).join() {
+            // On a slope, speed is along the incline rather than flat
+            let along = on_slopes.get(entity).map_or(1.0, |slope| 1.0 / (1.0 + slope.gradient * slope.gradient).sqrt());
+            pos.x += vel.x * along * dt;
             pos.y += vel.y * dt;
-            
-            // Screen bounds
-            pos.x = pos.x.clamp(0.0, 760.0); 
+
+            // Level bounds (horizontal only, falling out is handled elsewhere),
+            // applied to the collision shape rather than the sprite
+            let (offset, width) = body_shape(pos, colliders.get(entity), renderables.get(entity))
+                .map_or((0.0, 0.0), |shape| {
+                    let (x, _, width, _) = shape.bounds();
+                    (x - pos.x, width)
+                });
+            let min_x = bounds.min_x - offset;
+            pos.x = pos.x.clamp(min_x, (bounds.max_x - offset - width).max(min_x));
         }
     }
 }
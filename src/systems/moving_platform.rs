@@ -0,0 +1,94 @@
+use specs::{System, ReadStorage, WriteStorage, Join, Read, Entities, Entity};
+use crate::components::{Position, Velocity, MovingPlatform, Grounded, Collider, Renderable};
+use crate::spatial::SpatialGrid;
+use crate::systems::collision::body_shape;
+
+/// How far a rider's feet may be from the platform's top and still ride it.
+const RIDE_EPSILON: f32 = 1.0;
+
+/// Steers each `MovingPlatform` along its path and moves whatever stands on
+/// it by the same amount, so riders keep their place without relying on
+/// friction or gravity.
+pub struct MovingPlatformSystem;
+
+impl<'a> System<'a> for MovingPlatformSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+        WriteStorage<'a, MovingPlatform>,
+        ReadStorage<'a, Grounded>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Renderable>,
+        Read<'a, SpatialGrid>,
+        Read<'a, f32>,
+    );
+
+    fn run(&mut self, (entities, mut positions, mut velocities, mut platforms, grounded, colliders, renderables, grid, delta_time): Self::SystemData) {
+        let dt = *delta_time;
+
+        // Platform velocities for this step, and how far each will move
+        let mut moves: Vec<(Entity, (f32, f32))> = Vec::new();
+        for (entity, platform, pos) in (&entities, &mut platforms, &positions).join() {
+            let velocity = steer(platform, (pos.x, pos.y), dt);
+            velocities.insert(entity, Velocity { x: velocity.0, y: velocity.1 }).ok();
+            if velocity != (0.0, 0.0) {
+                moves.push((entity, (velocity.0 * dt, velocity.1 * dt)));
+            }
+        }
+
+        // Carry riders: grounded bodies whose feet are on a moving platform
+        for (platform, delta) in moves {
+            let top = match positions.get(platform).and_then(|pos| body_shape(pos, colliders.get(platform), renderables.get(platform))) {
+                Some(shape) => shape.bounds(),
+                None => continue,
+            };
+            let strip = (top.0, top.1 - RIDE_EPSILON, top.2, RIDE_EPSILON * 2.0);
+            for rider in grid.query_aabb(strip) {
+                if rider == platform || !grounded.contains(rider) {
+                    continue;
+                }
+                let pos = match positions.get_mut(rider) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                let riding = body_shape(pos, colliders.get(rider), renderables.get(rider)).is_some_and(|shape| {
+                    let body = shape.bounds();
+                    (body.1 + body.3 - top.1).abs() < RIDE_EPSILON && body.0 < top.0 + top.2 && body.0 + body.2 > top.0
+                });
+                if riding {
+                    pos.x += delta.0;
+                    pos.y += delta.1;
+                }
+            }
+        }
+    }
+}
+
+/// Velocity that takes the platform at `pos` towards its target waypoint,
+/// arriving exactly rather than overshooting. Counts down waits and moves on
+/// to the next waypoint on arrival.
+fn steer(platform: &mut MovingPlatform, pos: (f32, f32), dt: f32) -> (f32, f32) {
+    let origin = *platform.origin.get_or_insert(pos);
+    if platform.speed <= 0.0 {
+        return (0.0, 0.0);
+    }
+    if platform.waiting > 0.0 {
+        platform.waiting -= dt;
+        return (0.0, 0.0);
+    }
+
+    let waypoint = match platform.waypoint(platform.target) {
+        Some(waypoint) => waypoint,
+        None => return (0.0, 0.0),
+    };
+    let to = (origin.0 + waypoint.0 - pos.0, origin.1 + waypoint.1 - pos.1);
+    let distance = (to.0 * to.0 + to.1 * to.1).sqrt();
+    if distance <= platform.speed * dt {
+        platform.waiting = platform.wait;
+        platform.advance();
+        (to.0 / dt, to.1 / dt)
+    } else {
+        (to.0 / distance * platform.speed, to.1 / distance * platform.speed)
+    }
+}
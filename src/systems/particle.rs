@@ -1,7 +1,13 @@
-use specs::{System, ReadStorage, Entities, Join, LazyUpdate, Read, WriteStorage, Builder};
-use crate::components::{Position, ParticleEmitter, Renderable, Velocity, Lifetime};
+use specs::{System, ReadStorage, Entities, Join, LazyUpdate, Read, Write, WriteStorage, Builder};
+use crate::components::{Position, ParticleEmitter, Lifetime};
+use crate::prefab::{ComponentDef, PrefabRegistry};
+use crate::resources::GameRng;
 use rand::Rng;
 
+/// Prefab every emitted particle is built from. Its color, lifetime and
+/// velocity are replaced per particle.
+const PARTICLE_PREFAB: &str = "particle";
+
 pub struct ParticleSystem;
 
 impl<'a> System<'a> for ParticleSystem {
@@ -12,35 +18,27 @@ impl<'a> System<'a> for ParticleSystem {
         Read<'a, LazyUpdate>,
         WriteStorage<'a, Lifetime>,
         Read<'a, f32>,
+        Write<'a, GameRng>,
+        Read<'a, PrefabRegistry>,
     );
 
-    fn run(&mut self, (entities, positions, emitters, lazy, mut lifetimes, delta_time): Self::SystemData) {
-        let mut rng = rand::thread_rng();
+    fn run(&mut self, (entities, positions, emitters, lazy, mut lifetimes, delta_time, mut rng, prefabs): Self::SystemData) {
         let dt = *delta_time;
+        let particle = prefabs.resolve(PARTICLE_PREFAB).unwrap_or_default();
 
         for (_entity, pos, emitter) in (&entities, &positions, &emitters).join() {
             if rng.gen::<f32>() < emitter.rate * dt * 60.0 { // Rate per second approx
-                let particle_pos = Position {
-                    x: pos.x,
-                    y: pos.y,
-                };
-
-                let particle_vel = Velocity {
-                    x: rng.gen_range(-50.0..50.0),
-                    y: rng.gen_range(-50.0..50.0),
-                };
+                let velocity = (rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0));
 
-                lazy.create_entity(&entities)
-                    .with(particle_pos)
-                    .with(particle_vel)
-                    .with(Renderable {
-                        width: 4.0,
-                        height: 4.0,
-                        color: emitter.color,
-                    })
-                    .with(Lifetime {
-                        remaining: emitter.lifetime,
+                let builder = lazy.create_entity(&entities).with(Position { x: pos.x, y: pos.y });
+                particle.iter()
+                    .map(|component| match *component {
+                        ComponentDef::Renderable { width, height, .. } => ComponentDef::Renderable { width, height, color: emitter.color },
+                        ComponentDef::Velocity { .. } => ComponentDef::Velocity { x: velocity.0, y: velocity.1 },
+                        ComponentDef::Lifetime { .. } => ComponentDef::Lifetime { remaining: emitter.lifetime },
+                        ref other => other.clone(),
                     })
+                    .fold(builder, |builder, component| component.add_to(builder))
                     .build();
             }
         }
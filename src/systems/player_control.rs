@@ -0,0 +1,88 @@
+use specs::{System, ReadStorage, WriteStorage, Join, Read, Entities};
+use crate::components::{CharacterController, Player, Grounded, Knockback, Dead, OneWay, DropThrough, Layers};
+use crate::spatial::SpatialGrid;
+use crate::utils::input::{ActionState, InputAction};
+
+/// How long down + jump lets a player fall through one-way platforms.
+const DROP_THROUGH_TIME: f32 = 0.25;
+
+/// How far below a grounded player's feet to look for the platform it
+/// stands on.
+const GROUND_PROBE: f32 = 1.0;
+
+/// How far in from the player's sides the ground is looked for, so a
+/// platform it only touches side-on does not count.
+const FOOT_INSET: f32 = 1.0;
+
+/// Turns the `ActionState` into intent on each player's `CharacterController`.
+pub struct PlayerControlSystem;
+
+impl<'a> System<'a> for PlayerControlSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, CharacterController>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Grounded>,
+        ReadStorage<'a, Knockback>,
+        ReadStorage<'a, Dead>,
+        ReadStorage<'a, OneWay>,
+        WriteStorage<'a, DropThrough>,
+        Read<'a, ActionState>,
+        Read<'a, SpatialGrid>,
+        Read<'a, f32>,
+    );
+
+    fn run(&mut self, (entities, mut controllers, players, grounded, knockbacks, dead, one_ways, mut drop_throughs, actions, grid, delta_time): Self::SystemData) {
+        let move_x = actions.move_x();
+
+        let mut finished = Vec::new();
+        for (entity, drop) in (&entities, &mut drop_throughs).join() {
+            drop.remaining -= *delta_time;
+            if drop.remaining <= 0.0 {
+                finished.push(entity);
+            }
+        }
+        for entity in finished {
+            drop_throughs.remove(entity);
+        }
+
+        // Players get a controller with the default feel if they lack one
+        for (entity, _) in (&entities, &players).join() {
+            if let Ok(entry) = controllers.entry(entity) {
+                entry.or_insert_with(CharacterController::default);
+            }
+        }
+
+        for (entity, controller, _) in (&entities, &mut controllers, &players).join() {
+            // Knocked-back and dead players ignore input
+            if knockbacks.contains(entity) || dead.contains(entity) {
+                controller.move_x = 0.0;
+                controller.jump_pressed = false;
+                controller.jump_held = false;
+                continue;
+            }
+
+            // Analog input scales speed by stick magnitude
+            controller.move_x = move_x;
+            controller.jump_held = actions.pressed(InputAction::Jump);
+            controller.jump_pressed = actions.just_pressed(InputAction::Jump);
+
+            if controller.jump_pressed && actions.pressed(InputAction::MoveDown) && grounded.contains(entity) {
+                // Down + jump on a one-way platform drops through it instead.
+                // Either foot may be on it when standing across two platforms
+                let solid = grid.filter(entity).map_or(Layers::PLATFORM, |filter| filter.blocks);
+                let on_one_way = grid.rect(entity).is_some_and(|body| {
+                    let bottom = body.1 + body.3;
+                    [body.0 + FOOT_INSET, body.0 + body.2 - FOOT_INSET].iter().any(|&x| {
+                        grid.segment_cast((x, bottom), (x, bottom + GROUND_PROBE), solid)
+                            .is_some_and(|hit| one_ways.contains(hit.entity))
+                    })
+                });
+                if on_one_way {
+                    drop_throughs.insert(entity, DropThrough { remaining: DROP_THROUGH_TIME }).ok();
+                    controller.jump_pressed = false;
+                }
+            }
+        }
+    }
+}
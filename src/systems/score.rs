@@ -0,0 +1,53 @@
+use specs::shrev::{EventChannel, ReaderId};
+use specs::{System, SystemData, ReadStorage, WriteStorage, Read, Entities, World};
+use crate::components::{Collectible, Player};
+use crate::events::CollisionEvent;
+
+/// Points awarded for each collectible picked up.
+const COLLECTIBLE_SCORE: i32 = 10;
+
+/// Removes collectibles a player touches and adds to the player's score.
+#[derive(Default)]
+pub struct ScoreSystem {
+    reader: Option<ReaderId<CollisionEvent>>,
+}
+
+impl<'a> System<'a> for ScoreSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Collectible>,
+        WriteStorage<'a, Player>,
+        Read<'a, EventChannel<CollisionEvent>>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(world.fetch_mut::<EventChannel<CollisionEvent>>().register_reader());
+    }
+
+    fn run(&mut self, (entities, collectibles, mut players, events): Self::SystemData) {
+        let reader = self.reader.as_mut().expect("ScoreSystem::setup was not called");
+        for event in events.read(reader) {
+            let contact = match event {
+                CollisionEvent::Started(contact) => contact,
+                CollisionEvent::Ended { .. } => continue,
+            };
+            // Either side may be the player, depending on which moved
+            let (player, collectible) = if players.contains(contact.a) && collectibles.contains(contact.b) {
+                (contact.a, contact.b)
+            } else if players.contains(contact.b) && collectibles.contains(contact.a) {
+                (contact.b, contact.a)
+            } else {
+                continue;
+            };
+            // Two players may reach the same coin on the same step
+            if !entities.is_alive(collectible) {
+                continue;
+            }
+            entities.delete(collectible).unwrap();
+            if let Some(player) = players.get_mut(player) {
+                player.score += COLLECTIBLE_SCORE;
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use specs::storage::ComponentEvent;
+use specs::{System, SystemData, ReadStorage, Join, Write, Entities, BitSet, ReaderId, World, WorldExt};
+use crate::components::{Position, Renderable, Velocity, Collider, CollisionFilter, Layers, Player, Enemy, Platform, Collectible, Goal};
+use crate::spatial::SpatialGrid;
+use crate::systems::collision::body_shape;
+
+/// Keeps the `SpatialGrid` resource in step with the world: every body on a
+/// collision layer, stored as its collision shape with its
+/// `CollisionFilter`. Player, enemy, platform, collectible and goal
+/// markers put an entity on the matching layer; a `Collider` can change
+/// that.
+///
+/// Bodies with a `Velocity` are refreshed every step. Static bodies are only
+/// indexed when their `Position` is inserted or changed, and every entity
+/// leaves the grid when its `Position` is removed or it is deleted, so the
+/// cost of a step does not grow with the number of static tiles. Run
+/// without `setup`, e.g. through `run_now`, it indexes every entity once.
+#[derive(Default)]
+pub struct SpatialIndexSystem {
+    reader: Option<ReaderId<ComponentEvent>>,
+}
+
+impl<'a> System<'a> for SpatialIndexSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Velocity>,
+        ReadStorage<'a, Renderable>,
+        ReadStorage<'a, Collider>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Enemy>,
+        ReadStorage<'a, Platform>,
+        ReadStorage<'a, Collectible>,
+        ReadStorage<'a, Goal>,
+        Write<'a, SpatialGrid>,
+    );
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.reader = Some(world.write_storage::<Position>().register_reader());
+    }
+
+    fn run(&mut self, (entities, positions, velocities, renderables, colliders, players, enemies, platforms, collectibles, goals, mut grid): Self::SystemData) {
+        let mut dirty = BitSet::new();
+        match self.reader.as_mut() {
+            Some(reader) => {
+                for event in positions.channel().read(reader) {
+                    match *event {
+                        ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                            dirty.add(id);
+                        },
+                        ComponentEvent::Removed(id) => {
+                            dirty.remove(id);
+                            grid.remove_index(id);
+                        },
+                    }
+                }
+            },
+            None => {
+                for (entity, _) in (&entities, &positions).join() {
+                    dirty.add(entity.id());
+                }
+            },
+        }
+        for (entity, _) in (&entities, &velocities).join() {
+            dirty.add(entity.id());
+        }
+
+        for (entity, pos, _) in (&entities, &positions, &dirty).join() {
+            let kind = [
+                (players.contains(entity), Layers::PLAYER),
+                (enemies.contains(entity), Layers::ENEMY),
+                (platforms.contains(entity), Layers::PLATFORM),
+                (collectibles.contains(entity), Layers::COLLECTIBLE),
+                (goals.contains(entity), Layers::GOAL),
+            ].iter().filter(|(has, _)| *has).fold(Layers::NONE, |kind, (_, layer)| kind | *layer);
+            let filter = CollisionFilter::resolve(colliders.get(entity), kind);
+            let shape = body_shape(pos, colliders.get(entity), renderables.get(entity));
+            match shape.filter(|_| !filter.layers.is_empty()) {
+                Some(shape) => grid.update_shape(entity, shape, filter),
+                None => grid.remove(entity),
+            }
+        }
+    }
+}
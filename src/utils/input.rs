@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Logical player input, independent of the device that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    Jump,
+    Pause,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 5] = [
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::MoveDown,
+        InputAction::Jump,
+        InputAction::Pause,
+    ];
+}
+
+impl fmt::Display for InputAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputAction::MoveLeft => "MoveLeft",
+            InputAction::MoveRight => "MoveRight",
+            InputAction::MoveDown => "MoveDown",
+            InputAction::Jump => "Jump",
+            InputAction::Pause => "Pause",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for InputAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InputAction::ALL.iter()
+            .copied()
+            .find(|action| action.to_string() == s)
+            .ok_or_else(|| format!("unknown input action '{}'", s))
+    }
+}
+
+/// Logical analog input, in `-1.0..=1.0` after the deadzone is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAxis {
+    MoveX,
+}
+
+impl fmt::Display for InputAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputAxis::MoveX => f.write_str("MoveX"),
+        }
+    }
+}
+
+impl FromStr for InputAxis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "MoveX" => Ok(InputAxis::MoveX),
+            _ => Err(format!("unknown input axis '{}'", s)),
+        }
+    }
+}
+
+/// A single change to the logical input, from any device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Action(InputAction, bool),
+    Axis(InputAxis, f32),
+}
+
+/// Rescales a raw axis value so that `-deadzone..deadzone` reads as zero and
+/// the remaining range still reaches `-1.0..=1.0`.
+pub fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude <= deadzone || deadzone >= 1.0 {
+        return 0.0;
+    }
+    let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+    scaled.copysign(value)
+}
+
+/// Maps physical keys and gamepad buttons to logical actions. Key names are
+/// the ones SDL reports through `Keycode::name`, e.g. `Left`, `Space` or `A`;
+/// gamepad buttons use SDL's button names with a `Pad:` prefix, e.g. `Pad:a`
+/// or `Pad:dpleft`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMap {
+    bindings: HashMap<String, InputAction>,
+    /// Analog stick values below this magnitude are ignored.
+    pub deadzone: f32,
+}
+
+impl InputMap {
+    pub const DEFAULT_DEADZONE: f32 = 0.2;
+
+    pub fn empty() -> Self {
+        InputMap { bindings: HashMap::new(), deadzone: InputMap::DEFAULT_DEADZONE }
+    }
+
+    /// Binds `key` to `action`, replacing any previous binding for that key.
+    pub fn bind(&mut self, key: &str, action: InputAction) {
+        self.bindings.insert(key.to_string(), action);
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        self.bindings.remove(key);
+    }
+
+    pub fn action_for(&self, key: &str) -> Option<InputAction> {
+        self.bindings.get(key).copied()
+    }
+
+    /// All keys bound to `action`, sorted by name.
+    pub fn keys_for(&self, action: InputAction) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.bindings.iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key.as_str())
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<InputMap> {
+        let text = fs::read_to_string(path)?;
+        InputMap::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Parses a controls file made of `Action = Key, Key` lines plus an
+    /// optional `Deadzone = 0.2` line. Actions that are not listed keep no
+    /// bindings.
+    pub fn parse(text: &str) -> Result<InputMap, String> {
+        let mut map = InputMap::empty();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (action, keys) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected 'Action = Key, ...'", line_no))?;
+            if action.trim() == "Deadzone" {
+                map.deadzone = keys.trim().parse()
+                    .map_err(|_| format!("line {}: invalid deadzone '{}'", line_no, keys.trim()))?;
+                continue;
+            }
+            let action: InputAction = action.trim().parse()
+                .map_err(|e| format!("line {}: {}", line_no, e))?;
+            for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+                map.bind(key, action);
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let mut map = InputMap::empty();
+        map.bind("Left", InputAction::MoveLeft);
+        map.bind("Right", InputAction::MoveRight);
+        map.bind("Down", InputAction::MoveDown);
+        map.bind("Space", InputAction::Jump);
+        map.bind("P", InputAction::Pause);
+        map.bind("Pad:dpleft", InputAction::MoveLeft);
+        map.bind("Pad:dpright", InputAction::MoveRight);
+        map.bind("Pad:dpdown", InputAction::MoveDown);
+        map.bind("Pad:a", InputAction::Jump);
+        map.bind("Pad:start", InputAction::Pause);
+        map
+    }
+}
+
+impl fmt::Display for InputMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in InputAction::ALL {
+            writeln!(f, "{} = {}", action, self.keys_for(action).join(", "))?;
+        }
+        writeln!(f, "Deadzone = {}", self.deadzone)
+    }
+}
+
+/// Per-tick state of every logical action and axis, read by gameplay systems.
+#[derive(Debug, Clone, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+    just_released: HashSet<InputAction>,
+    axes: HashMap<InputAxis, f32>,
+    just_centered: HashSet<InputAxis>,
+}
+
+impl ActionState {
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Action(action, true) => self.press(action),
+            InputEvent::Action(action, false) => self.release(action),
+            InputEvent::Axis(axis, value) => self.set_axis(axis, value),
+        }
+    }
+
+    pub fn press(&mut self, action: InputAction) {
+        if self.pressed.insert(action) {
+            self.just_pressed.insert(action);
+        }
+    }
+
+    pub fn release(&mut self, action: InputAction) {
+        if self.pressed.remove(&action) {
+            self.just_released.insert(action);
+        }
+    }
+
+    pub fn set_axis(&mut self, axis: InputAxis, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        let previous = self.axes.insert(axis, value).unwrap_or(0.0);
+        if previous != 0.0 && value == 0.0 {
+            self.just_centered.insert(axis);
+        }
+    }
+
+    /// Current analog value of the axis, zero if it was never moved.
+    pub fn axis(&self, axis: InputAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// True only on the tick the axis returned to zero.
+    pub fn just_centered(&self, axis: InputAxis) -> bool {
+        self.just_centered.contains(&axis)
+    }
+
+    /// Horizontal movement in `-1.0..=1.0`. Digital left/right take priority;
+    /// otherwise the analog `MoveX` axis is used, so its magnitude carries
+    /// through to partial speed.
+    pub fn move_x(&self) -> f32 {
+        let left = self.pressed(InputAction::MoveLeft);
+        let right = self.pressed(InputAction::MoveRight);
+        match (left, right) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            (true, true) => 0.0,
+            (false, false) => self.axis(InputAxis::MoveX),
+        }
+    }
+
+    /// The events that take a default `ActionState` to this one's held
+    /// actions and axis values.
+    pub fn held_events(&self) -> Vec<InputEvent> {
+        let mut events: Vec<InputEvent> = InputAction::ALL.into_iter()
+            .filter(|action| self.pressed(*action))
+            .map(|action| InputEvent::Action(action, true))
+            .collect();
+        let move_x = self.axis(InputAxis::MoveX);
+        if move_x != 0.0 {
+            events.push(InputEvent::Axis(InputAxis::MoveX, move_x));
+        }
+        events
+    }
+
+    /// True while the action is held down.
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// True only on the tick the action went down.
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// True only on the tick the action was let go.
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Forgets this tick's transitions; called by the engine after each step.
+    pub fn clear_transitions(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.just_centered.clear();
+    }
+}
+
+/// Tracks which physical bindings are down, so an action bound to several
+/// keys is only released once the last of them goes up.
+#[derive(Debug, Clone, Default)]
+pub struct HeldBindings {
+    held: HashMap<String, InputAction>,
+}
+
+impl HeldBindings {
+    /// Marks `binding` as held. Returns true if this is the first binding
+    /// holding `action`; repeated presses of a held binding return false.
+    pub fn press(&mut self, binding: &str, action: InputAction) -> bool {
+        if self.held.contains_key(binding) {
+            return false;
+        }
+        let first = !self.held.values().any(|held| *held == action);
+        self.held.insert(binding.to_string(), action);
+        first
+    }
+
+    /// Lets go of `binding`. Returns its action if no other binding still
+    /// holds it.
+    pub fn release(&mut self, binding: &str) -> Option<InputAction> {
+        let action = self.held.remove(binding)?;
+        if self.held.values().any(|held| *held == action) {
+            None
+        } else {
+            Some(action)
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+/// What each connected controller is holding, so unplugging one releases
+/// its own buttons and stick without touching the others.
+#[derive(Debug, Clone, Default)]
+pub struct PadInputs {
+    buttons: HashMap<u32, HashSet<String>>,
+    move_x: BTreeMap<u32, f32>,
+}
+
+impl PadInputs {
+    /// Records a button on controller `pad` going down or up.
+    pub fn button(&mut self, pad: u32, button: &str, pressed: bool) {
+        let held = self.buttons.entry(pad).or_default();
+        if pressed {
+            held.insert(button.to_string());
+        } else {
+            held.remove(button);
+        }
+    }
+
+    /// Records the stick value of controller `pad` and returns the combined
+    /// `MoveX` value.
+    pub fn set_move_x(&mut self, pad: u32, value: f32) -> f32 {
+        self.move_x.insert(pad, value);
+        self.move_x()
+    }
+
+    /// The stick value furthest from the centre across every controller.
+    pub fn move_x(&self) -> f32 {
+        self.move_x.values().fold(0.0, |best, &value| if value.abs() > best.abs() { value } else { best })
+    }
+
+    /// Forgets controller `pad`. Returns the buttons it was still holding,
+    /// sorted by name, and the combined `MoveX` value of the others.
+    pub fn remove(&mut self, pad: u32) -> (Vec<String>, f32) {
+        let mut held: Vec<String> = self.buttons.remove(&pad).unwrap_or_default().into_iter().collect();
+        held.sort_unstable();
+        self.move_x.remove(&pad);
+        (held, self.move_x())
+    }
+}
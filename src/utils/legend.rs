@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use crate::components::PathMode;
+use crate::prefab::{ComponentDef, BASE_TILE_SIZE};
+
+/// Speed of platforms given a `path` without one, in pixels per second.
+const DEFAULT_PATH_SPEED: f32 = 80.0;
+
+/// Component values that replace a prefab's defaults. Sizes and offsets are
+/// in pixels, for `BASE_TILE_SIZE` tiles in ASCII levels; anything left as
+/// `None` keeps the prefab's value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TileOverrides {
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    /// Shift from the tile's top-left corner, e.g. to sit a short tile on
+    /// the floor of its cell.
+    pub offset: Option<(f32, f32)>,
+    pub color: Option<(u8, u8, u8)>,
+    pub velocity: Option<(f32, f32)>,
+    /// Gravity scale. `true` and `false` are 1 and 0, and 0 removes
+    /// `Gravity` altogether.
+    pub gravity: Option<f32>,
+    pub speed: Option<f32>,
+    pub jump_force: Option<f32>,
+    pub health: Option<i32>,
+    pub damage: Option<i32>,
+    /// Waypoints of a moving platform, relative to its tile. Adds a
+    /// `MovingPlatform` if the prefab has none.
+    pub path: Option<Vec<(f32, f32)>>,
+    pub wait: Option<f32>,
+    pub mode: Option<PathMode>,
+    /// Surface properties. Adds a `PhysicsMaterial` if the prefab has none.
+    pub friction: Option<f32>,
+    pub bounciness: Option<f32>,
+}
+
+impl TileOverrides {
+    /// Sets one override from its `key=value` text form.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value '{}' for '{}'", value, key);
+        match key {
+            "width" => self.width = Some(value.parse().map_err(|_| invalid())?),
+            "height" => self.height = Some(value.parse().map_err(|_| invalid())?),
+            "offset" => self.offset = Some(parse_pair(value).ok_or_else(invalid)?),
+            "color" => self.color = Some(parse_color(value).ok_or_else(invalid)?),
+            "velocity" => self.velocity = Some(parse_pair(value).ok_or_else(invalid)?),
+            "gravity" => self.gravity = Some(match value {
+                "true" => 1.0,
+                "false" => 0.0,
+                _ => value.parse().map_err(|_| invalid())?,
+            }),
+            "speed" => self.speed = Some(value.parse().map_err(|_| invalid())?),
+            "jump_force" => self.jump_force = Some(value.parse().map_err(|_| invalid())?),
+            "health" => self.health = Some(value.parse().map_err(|_| invalid())?),
+            "damage" => self.damage = Some(value.parse().map_err(|_| invalid())?),
+            "path" => {
+                let points: Option<Vec<_>> = value.split(';').map(parse_pair).collect();
+                self.path = Some(points.filter(|points| !points.is_empty()).ok_or_else(invalid)?);
+            },
+            "wait" => self.wait = Some(value.parse().map_err(|_| invalid())?),
+            "mode" => self.mode = Some(match value {
+                "linear" => PathMode::Linear,
+                "pingpong" => PathMode::PingPong,
+                _ => return Err(invalid()),
+            }),
+            "friction" => self.friction = Some(value.parse().ok().filter(|friction: &f32| *friction >= 0.0).ok_or_else(invalid)?),
+            "bounciness" => self.bounciness = Some(value.parse().ok().filter(|bounciness: &f32| *bounciness >= 0.0).ok_or_else(invalid)?),
+            _ => return Err(format!("unknown override '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Applies the overrides to a prefab's resolved component list. The
+    /// offset is positional and is applied by the level loader instead.
+    pub fn apply(&self, components: &mut Vec<ComponentDef>) {
+        for component in components.iter_mut() {
+            match component {
+                ComponentDef::Renderable { width, height, color } => {
+                    *width = self.width.unwrap_or(*width);
+                    *height = self.height.unwrap_or(*height);
+                    *color = self.color.unwrap_or(*color);
+                },
+                ComponentDef::Player { speed, jump_force } => {
+                    *speed = self.speed.unwrap_or(*speed);
+                    *jump_force = self.jump_force.unwrap_or(*jump_force);
+                },
+                ComponentDef::Health { max } => *max = self.health.unwrap_or(*max),
+                ComponentDef::MovingPlatform { path, speed, wait, mode } => {
+                    *path = self.path.clone().unwrap_or_else(|| path.clone());
+                    *speed = self.speed.unwrap_or(*speed);
+                    *wait = self.wait.unwrap_or(*wait);
+                    *mode = self.mode.unwrap_or(*mode);
+                },
+                _ => {},
+            }
+        }
+
+        let moving = components.iter().any(|component| matches!(component, ComponentDef::MovingPlatform { .. }));
+        if let (Some(path), false) = (&self.path, moving) {
+            components.push(ComponentDef::MovingPlatform {
+                path: path.clone(),
+                speed: self.speed.unwrap_or(DEFAULT_PATH_SPEED),
+                wait: self.wait.unwrap_or(0.0),
+                mode: self.mode.unwrap_or_default(),
+            });
+            if !components.iter().any(|component| matches!(component, ComponentDef::Velocity { .. })) {
+                components.push(ComponentDef::Velocity { x: 0.0, y: 0.0 });
+            }
+        }
+
+        if let Some(amount) = self.damage {
+            components.retain(|component| !matches!(component, ComponentDef::Damage { .. }));
+            components.push(ComponentDef::Damage { amount });
+        }
+
+        if let Some((x, y)) = self.velocity {
+            components.retain(|component| !matches!(component, ComponentDef::Velocity { .. }));
+            components.push(ComponentDef::Velocity { x, y });
+        }
+        if let Some(scale) = self.gravity {
+            components.retain(|component| !matches!(component, ComponentDef::Gravity { .. }));
+            if scale != 0.0 {
+                components.push(ComponentDef::Gravity { scale });
+            }
+        }
+
+        if self.friction.is_some() || self.bounciness.is_some() {
+            let mut material = components.iter().find_map(|component| match component {
+                ComponentDef::PhysicsMaterial(material) => Some(*material),
+                _ => None,
+            }).unwrap_or_default();
+            material.friction = self.friction.unwrap_or(material.friction);
+            material.bounciness = self.bounciness.unwrap_or(material.bounciness);
+            components.retain(|component| !matches!(component, ComponentDef::PhysicsMaterial(_)));
+            components.push(ComponentDef::PhysicsMaterial(material));
+        }
+    }
+}
+
+/// What a single legend character spawns: a prefab from the
+/// `PrefabRegistry`, by name, plus per-tile overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub prefab: String,
+    pub overrides: TileOverrides,
+}
+
+impl LegendEntry {
+    pub fn new(prefab: &str) -> Self {
+        LegendEntry { prefab: prefab.to_string(), overrides: TileOverrides::default() }
+    }
+
+    /// Parses `prefab key=value key=value`.
+    pub fn parse(text: &str) -> Result<LegendEntry, String> {
+        let mut words = text.split_whitespace();
+        let prefab = words.next().ok_or("missing prefab name")?.to_string();
+        let mut overrides = TileOverrides::default();
+        for word in words {
+            let (key, value) = word.split_once('=')
+                .ok_or_else(|| format!("expected 'key=value', found '{}'", word))?;
+            overrides.set(key, value)?;
+        }
+        Ok(LegendEntry { prefab, overrides })
+    }
+}
+
+/// Maps level map characters to the prefabs they spawn. Space is always
+/// empty and cannot be bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Legend {
+    entries: HashMap<char, LegendEntry>,
+}
+
+impl Legend {
+    pub fn empty() -> Self {
+        Legend { entries: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, ch: char, entry: LegendEntry) {
+        self.entries.insert(ch, entry);
+    }
+
+    pub fn get(&self, ch: char) -> Option<&LegendEntry> {
+        self.entries.get(&ch)
+    }
+
+    /// Adds every entry of `other`, replacing existing ones.
+    pub fn extend(&mut self, other: Legend) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Parses a single `X = prefab key=value ...` definition.
+    pub fn parse_entry(line: &str) -> Result<(char, LegendEntry), String> {
+        let (ch, definition) = line.split_once('=')
+            .ok_or("expected 'X = prefab key=value ...'")?;
+        let mut chars = ch.trim().chars();
+        let ch = match (chars.next(), chars.next()) {
+            (Some(ch), None) if ch != ' ' => ch,
+            _ => return Err(format!("legend key '{}' must be a single character", ch.trim())),
+        };
+        Ok((ch, LegendEntry::parse(definition)?))
+    }
+
+    /// Parses a legend file: one `X = prefab key=value ...` line per
+    /// character. Errors carry the 1-based line number.
+    pub fn parse(text: &str) -> Result<Legend, (usize, String)> {
+        let mut legend = Legend::empty();
+        for (index, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            // `#` is a common tile character, so only `//` starts a comment
+            if trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+            let (ch, entry) = Legend::parse_entry(trimmed).map_err(|e| (index + 1, e))?;
+            legend.insert(ch, entry);
+        }
+        Ok(legend)
+    }
+}
+
+impl Default for Legend {
+    /// The classic tile set: `#` platform, `-` ledge, `_` half-height
+    /// platform, `/` and `\\` 45° slopes, `[` `]` and `{` `}` pairs of
+    /// slopes rising 1 in 2 (about 26.6°), `P` player, `E` enemy, `C` coin,
+    /// `G` goal and `?` tutorial hint.
+    fn default() -> Self {
+        let mut legend = Legend::empty();
+        legend.insert('#', LegendEntry::new("platform"));
+        legend.insert('-', LegendEntry::new("ledge"));
+        // Half-height tiles sit on the floor of their cell. The loader scales
+        // the offset with the level's tile size
+        let mut half = LegendEntry::new("half_platform");
+        half.overrides.offset = Some((0.0, BASE_TILE_SIZE / 2.0));
+        legend.insert('_', half);
+        legend.insert('/', LegendEntry::new("slope_up"));
+        legend.insert('\\', LegendEntry::new("slope_down"));
+        legend.insert('[', LegendEntry::new("slope_up_low"));
+        legend.insert(']', LegendEntry::new("slope_up_high"));
+        legend.insert('{', LegendEntry::new("slope_down_high"));
+        legend.insert('}', LegendEntry::new("slope_down_low"));
+        legend.insert('P', LegendEntry::new("player"));
+        legend.insert('E', LegendEntry::new("enemy"));
+        legend.insert('C', LegendEntry::new("coin"));
+        legend.insert('G', LegendEntry::new("goal"));
+        legend.insert('?', LegendEntry::new("hint"));
+        legend
+    }
+}
+
+pub(crate) fn parse_pair(value: &str) -> Option<(f32, f32)> {
+    let (a, b) = value.split_once(',')?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+pub(crate) fn parse_color(value: &str) -> Option<(u8, u8, u8)> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<u8>());
+    let color = (parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(color)
+}
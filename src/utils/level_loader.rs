@@ -1,63 +1,279 @@
-use specs::{World, WorldExt, Builder};
-use crate::components::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-pub fn load_level(world: &mut World, level_data: &str) {
+use specs::{World, WorldExt};
+use crate::prefab::{spawn_components, ComponentDef, PrefabRegistry, BASE_TILE_SIZE};
+use crate::resources::{PhysicsConfig, WorldBounds};
+use crate::utils::legend::{parse_color, parse_pair, Legend};
+
+const HEADER_SEPARATOR: &str = "---";
+
+/// Metadata from a level file's header. Stored as a `World` resource while
+/// the level is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelInfo {
+    pub name: String,
+    pub tile_size: f32,
+    /// Name of the level to load when this one is completed.
+    pub next: Option<String>,
+    pub background: (u8, u8, u8),
+    /// Music track for the frontend to play; the engine only stores it.
+    pub music: Option<String>,
+    /// Where the player starts, and respawns after falling out: the first
+    /// tile spawning a `Player`.
+    pub spawn: Option<(f32, f32)>,
+    /// Also stored as the `PhysicsConfig` resource.
+    pub physics: PhysicsConfig,
+}
+
+impl Default for LevelInfo {
+    fn default() -> Self {
+        LevelInfo {
+            name: String::new(),
+            tile_size: BASE_TILE_SIZE,
+            next: None,
+            background: (20, 20, 40),
+            music: None,
+            spawn: None,
+            physics: PhysicsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    /// Reading the file at `path` failed.
+    Io { path: PathBuf, error: io::Error },
+    /// A malformed header entry or map character. `line` and `column` are
+    /// 1-based positions in `file`, or in the level source when `file` is
+    /// `None`.
+    Parse { file: Option<PathBuf>, line: usize, column: usize, message: String },
+}
+
+impl LevelError {
+    fn parse(line: usize, column: usize, message: impl Into<String>) -> Self {
+        LevelError::Parse { file: None, line, column, message: message.into() }
+    }
+
+    fn io(path: &Path, error: io::Error) -> Self {
+        LevelError::Io { path: path.to_path_buf(), error }
+    }
+
+    /// Attributes a parse error without a file to the file at `path`.
+    fn in_file(self, path: &Path) -> Self {
+        match self {
+            LevelError::Parse { file: None, line, column, message } => {
+                LevelError::Parse { file: Some(path.to_path_buf()), line, column, message }
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            LevelError::Parse { file: Some(file), line, column, message } => {
+                write!(f, "{}:{}:{}: {}", file.display(), line, column, message)
+            },
+            LevelError::Parse { file: None, line, column, message } => write!(f, "{}:{}: {}", line, column, message),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LevelError::Io { error, .. } => Some(error),
+            LevelError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Reads a level file and loads it into the world. Errors carry the file's
+/// path, and a level without a `name:` is named after the file stem.
+pub fn load_level_file<P: AsRef<Path>>(world: &mut World, path: P) -> Result<LevelInfo, LevelError> {
+    let path = path.as_ref();
+    let level_data = fs::read_to_string(path).map_err(|e| LevelError::io(path, e))?;
+    let mut info = load_level_from(world, &level_data, path.parent()).map_err(|e| e.in_file(path))?;
+    if info.name.is_empty() {
+        info.name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        world.insert(info.clone());
+    }
+    Ok(info)
+}
+
+/// Spawns the entities described by a level and stores the level's extent in
+/// the `WorldBounds` resource, its header in the `LevelInfo` resource and
+/// its physics in the `PhysicsConfig` resource.
+///
+/// A level is an optional header of `key: value` lines (`name`, `tile_size`,
+/// `next`, `background`, `music`, `gravity`, `drag`, `rest_speed`, `kill_y`,
+/// `legend`, `tile`) ended by a `---` line,
+/// followed by an ASCII grid. Without a `---` line the whole text is the
+/// grid. Nothing is spawned if the level is malformed.
+///
+/// Map characters are looked up in the default `Legend`, then in the shared
+/// legend file named by `legend:`, then in the level's own `tile: X = ...`
+/// lines, later definitions winning. Each names a prefab in the world's
+/// `PrefabRegistry`; a character whose prefab is missing is reported at its
+/// first position in the map.
+///
+/// Prefabs and legend overrides are sized for `BASE_TILE_SIZE` tiles. With
+/// another `tile_size` their sizes and offsets are scaled to fit.
+pub fn load_level(world: &mut World, level_data: &str) -> Result<LevelInfo, LevelError> {
+    load_level_from(world, level_data, None)
+}
+
+/// Loads a level whose `legend:` path is resolved relative to `base_dir`.
+fn load_level_from(world: &mut World, level_data: &str, base_dir: Option<&Path>) -> Result<LevelInfo, LevelError> {
     let lines: Vec<&str> = level_data.lines().collect();
-    
-    for (y, line) in lines.iter().enumerate() {
+    let separator = lines.iter().position(|line| line.trim_end() == HEADER_SEPARATOR);
+
+    let (mut info, legend, grid_start) = match separator {
+        Some(index) => {
+            let (info, legend) = parse_header(&lines[..index], base_dir)?;
+            (info, legend, index + 1)
+        },
+        None => (LevelInfo::default(), Legend::default(), 0),
+    };
+    let grid = &lines[grid_start..];
+
+    // Resolve every character used before spawning anything, so a bad map
+    // leaves the world untouched
+    let tile = info.tile_size;
+    let scale = tile / BASE_TILE_SIZE;
+    let mut tiles: HashMap<char, (Vec<ComponentDef>, (f32, f32))> = HashMap::new();
+    {
+        let prefabs = world.read_resource::<PrefabRegistry>();
+        for (row, line) in grid.iter().enumerate() {
+            for (column, ch) in line.chars().enumerate() {
+                if ch == ' ' || tiles.contains_key(&ch) {
+                    continue;
+                }
+                let error = |message: String| LevelError::parse(grid_start + row + 1, column + 1, message);
+                let entry = legend.get(ch).ok_or_else(|| error(format!("unknown map character '{}'", ch)))?;
+                let mut components = prefabs.resolve(&entry.prefab)
+                    .map_err(|e| error(format!("map character '{}': {}", ch, e)))?;
+                entry.overrides.apply(&mut components);
+                components.iter_mut().for_each(|component| component.scale(scale));
+                let (offset_x, offset_y) = entry.overrides.offset.unwrap_or((0.0, 0.0));
+                tiles.insert(ch, (components, (offset_x * scale, offset_y * scale)));
+            }
+        }
+    }
+
+    let mut spawns = Vec::new();
+    for (y, line) in grid.iter().enumerate() {
         for (x, ch) in line.chars().enumerate() {
-            let pos_x = x as f32 * 40.0;
-            let pos_y = y as f32 * 40.0;
-            
-            match ch {
-                '#' => { // Platform
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Renderable { width: 40.0, height: 40.0, color: (100, 100, 100) })
-                        .with(Platform)
-                        .build();
-                },
-                'P' => { // Player
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Velocity { x: 0.0, y: 0.0 })
-                        .with(Renderable { width: 40.0, height: 40.0, color: (0, 255, 0) })
-                        .with(Player { speed: 200.0, jump_force: 600.0, score: 0 })
-                        .with(Health { current: 100, max: 100 })
-                        .with(Gravity)
-                        .build();
-                },
-                'E' => { // Enemy
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Velocity { x: 100.0, y: 0.0 })
-                        .with(Renderable { width: 40.0, height: 40.0, color: (255, 0, 0) })
-                        .with(Enemy)
-                        .with(Gravity)
-                        .build();
-                },
-                'C' => { // Coin
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Renderable { width: 20.0, height: 20.0, color: (255, 215, 0) })
-                        .with(Collectible)
-                        .build();
-                },
-                'G' => { // Goal
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Renderable { width: 40.0, height: 60.0, color: (0, 255, 255) }) // Cyan Portal
-                        .with(Goal)
-                        .build();
-                },
-                '?' => { // Tutorial Hint (Square)
-                    world.create_entity()
-                        .with(Position { x: pos_x, y: pos_y })
-                        .with(Renderable { width: 40.0, height: 40.0, color: (200, 200, 255) })
-                        .build();
-                },
-                _ => {}
+            if let Some((components, (offset_x, offset_y))) = tiles.get(&ch) {
+                spawns.push((components, (x as f32 * tile + offset_x, y as f32 * tile + offset_y)));
             }
         }
     }
+    info.spawn = spawns.iter().find(|(components, _)| spawns_player(components)).map(|(_, pos)| *pos);
+
+    let columns = grid.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    world.insert(WorldBounds::new(columns as f32 * tile, grid.len() as f32 * tile));
+    world.insert(info.physics);
+    world.insert(info.clone());
+    for (components, pos) in spawns {
+        spawn_components(world, components, pos);
+    }
+
+    Ok(info)
+}
+
+fn parse_header(lines: &[&str], base_dir: Option<&Path>) -> Result<(LevelInfo, Legend), LevelError> {
+    let mut info = LevelInfo::default();
+    let mut legend = Legend::default();
+    let mut inline_tiles = Legend::empty();
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_no = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = trimmed.split_once(':')
+            .ok_or_else(|| LevelError::parse(line_no, 1, "expected 'key: value' in level header"))?;
+        let value = value.trim();
+        // 1-based column of the first character of the value
+        let value_column = line.find(':').map_or(1, |i| {
+            let rest = &line[i + 1..];
+            i + 2 + rest.len() - rest.trim_start().len()
+        });
+
+        match key.trim() {
+            "name" => info.name = value.to_string(),
+            "tile_size" => {
+                info.tile_size = value.parse().ok()
+                    .filter(|size: &f32| *size > 0.0)
+                    .ok_or_else(|| LevelError::parse(line_no, value_column, format!("invalid tile size '{}'", value)))?;
+            },
+            "next" => info.next = Some(value.to_string()).filter(|next| !next.is_empty()),
+            "background" => {
+                info.background = parse_color(value)
+                    .ok_or_else(|| LevelError::parse(line_no, value_column, format!("invalid color '{}', expected 'r, g, b'", value)))?;
+            },
+            "music" => info.music = Some(value.to_string()).filter(|music| !music.is_empty()),
+            "gravity" | "drag" | "rest_speed" | "kill_y" => {
+                parse_physics(&mut info.physics, key.trim(), value)
+                    .map_err(|message| LevelError::parse(line_no, value_column, message))?;
+            },
+            "legend" => {
+                let path = base_dir.map_or_else(|| Path::new(value).to_path_buf(), |dir| dir.join(value));
+                let text = fs::read_to_string(&path).map_err(|e| LevelError::io(&path, e))?;
+                // Positions in the legend file are reported against that file
+                let shared = Legend::parse(&text).map_err(|(line, message)| LevelError::parse(line, 1, message).in_file(&path))?;
+                legend.extend(shared);
+            },
+            "tile" => {
+                let (ch, entry) = Legend::parse_entry(value)
+                    .map_err(|message| LevelError::parse(line_no, value_column, message))?;
+                inline_tiles.insert(ch, entry);
+            },
+            other => return Err(LevelError::parse(line_no, 1, format!("unknown header key '{}'", other))),
+        }
+    }
+
+    legend.extend(inline_tiles);
+    Ok((info, legend))
+}
+
+/// Whether a tile's components include a `Player`.
+pub(crate) fn spawns_player(components: &[ComponentDef]) -> bool {
+    components.iter().any(|component| matches!(component, ComponentDef::Player { .. }))
+}
+
+/// Applies one of the physics header entries (`gravity`, `drag`,
+/// `rest_speed` or `kill_y`) to `physics`.
+pub(crate) fn parse_physics(physics: &mut PhysicsConfig, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "gravity" => {
+            physics.gravity = parse_pair(value)
+                .ok_or_else(|| format!("invalid gravity '{}', expected 'x, y'", value))?;
+        },
+        "drag" => {
+            let unit = |drag: f32| (0.0..=1.0).contains(&drag);
+            (physics.ground_drag, physics.air_drag) = parse_pair(value)
+                .filter(|(ground, air)| unit(*ground) && unit(*air))
+                .ok_or_else(|| format!("invalid drag '{}', expected 'ground, air' between 0 and 1", value))?;
+        },
+        "rest_speed" => {
+            physics.rest_speed = value.parse().ok()
+                .filter(|speed: &f32| *speed >= 0.0)
+                .ok_or_else(|| format!("invalid rest speed '{}'", value))?;
+        },
+        "kill_y" => {
+            physics.kill_y = Some(value.parse().map_err(|_| format!("invalid kill line '{}'", value))?);
+        },
+        other => return Err(format!("unknown physics setting '{}'", other)),
+    }
+    Ok(())
 }
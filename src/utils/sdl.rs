@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use sdl2::controller::{Axis, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::GameControllerSubsystem;
+use sdl2::rect::Rect;
+use specs::World;
+use specs::WorldExt;
+use crate::components::{DrawOrder, Health, Invulnerable, Position, PreviousPosition, Renderable, Player, Slope};
+use crate::resources::Camera;
+use crate::utils::input::{apply_deadzone, InputAxis, InputMap, PadInputs};
+use crate::utils::level_loader::LevelInfo;
+use crate::{GameMode, GameState};
+
+/// Draws the world. `alpha` is the fixed-step interpolation factor from
+/// `GameState::alpha`; moving entities are drawn between their previous and
+/// current simulated positions.
+pub fn render_game(world: &World, canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, mode: GameMode, alpha: f32) -> Result<(), String> {
+    use specs::Join;
+
+    let positions = world.read_storage::<Position>();
+    let previous_positions = world.read_storage::<PreviousPosition>();
+    let renderables = world.read_storage::<Renderable>();
+    let draw_orders = world.read_storage::<DrawOrder>();
+    let invulnerables = world.read_storage::<Invulnerable>();
+    let slopes = world.read_storage::<Slope>();
+    let healths = world.read_storage::<Health>();
+    let players = world.read_storage::<Player>();
+    let entities = world.entities();
+    let camera = world.read_resource::<Camera>();
+
+    if mode == GameMode::Menu {
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(10, 10, 20));
+        canvas.clear();
+        // Light color for the "Start" hint
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(100, 100, 255));
+        canvas.fill_rect(Rect::new(300, 400, 200, 50))?;
+        return Ok(());
+    }
+
+    if mode == GameMode::GameOver {
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(50, 0, 0));
+        canvas.clear();
+        return Ok(());
+    } else if mode == GameMode::Win {
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 100, 0)); // Dark green for win
+        canvas.clear();
+        return Ok(());
+    } else {
+        // Gameplay background comes from the level header
+        let background = world.read_resource::<LevelInfo>().background;
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(background.0, background.1, background.2));
+        canvas.clear();
+    }
+    
+    // Render Platforms/Players/Collectibles, lowest draw layer first
+    let mut drawables: Vec<_> = (&entities, &positions, &renderables).join().collect();
+    drawables.sort_by_key(|(entity, _, _)| draw_orders.get(*entity).map_or(0, |order| order.layer));
+    for (entity, pos, render) in drawables {
+        // Blink while invulnerable after a hit
+        if invulnerables.get(entity).is_some_and(|inv| (inv.remaining * 10.0) as i32 % 2 == 1) {
+            continue;
+        }
+
+        let (world_x, world_y) = match previous_positions.get(entity) {
+            Some(prev) => (prev.x + (pos.x - prev.x) * alpha, prev.y + (pos.y - prev.y) * alpha),
+            None => (pos.x, pos.y),
+        };
+        let (x, y) = camera.world_to_screen(world_x, world_y, alpha);
+
+        // Skip anything outside the viewport
+        if x + render.width < 0.0 || y + render.height < 0.0
+            || x > camera.viewport_width || y > camera.viewport_height {
+            continue;
+        }
+
+        let color = render.color;
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(color.0, color.1, color.2));
+        if let Some(slope) = slopes.get(entity) {
+            // One column per pixel, from the surface down
+            let rect = (x, y, render.width, render.height);
+            for column in 0..render.width as i32 {
+                let top = slope.surface_y(rect, x + column as f32 + 0.5);
+                canvas.draw_line((x as i32 + column, top as i32), (x as i32 + column, (y + render.height) as i32 - 1))?;
+            }
+            continue;
+        }
+        canvas.fill_rect(Rect::new(
+            x as i32,
+            y as i32,
+            render.width as u32,
+            render.height as u32,
+        ))?;
+    }
+
+    // Render HUD (Score Bar)
+    for (entity, player) in (&entities, &players).join() {
+        // Draw score bar
+        canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 215, 0));
+        canvas.fill_rect(Rect::new(10, 10, (player.score as u32 * 2).min(200), 10))?;
+
+        // Draw health bar below it
+        if let Some(health) = healths.get(entity) {
+            let fraction = health.current.max(0) as f32 / health.max.max(1) as f32;
+            canvas.set_draw_color(sdl2::pixels::Color::RGB(200, 30, 30));
+            canvas.fill_rect(Rect::new(10, 25, (fraction * 200.0) as u32, 10))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps a keyboard event through the `InputMap` resource and queues the
+/// resulting action on the game. Unbound keys are ignored.
+pub fn handle_input(game_state: &mut GameState, keycode: Keycode, pressed: bool) {
+    game_state.key_input(&keycode.name(), pressed);
+}
+
+/// Opens game controllers as they are plugged in and feeds their buttons and
+/// left stick into the same logical input as the keyboard.
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    open: HashMap<u32, GameController>,
+    inputs: PadInputs,
+}
+
+impl Gamepads {
+    pub fn new(subsystem: GameControllerSubsystem) -> Self {
+        Gamepads { subsystem, open: HashMap::new(), inputs: PadInputs::default() }
+    }
+
+    /// Number of controllers currently connected.
+    pub fn connected(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Handles controller hotplug, button and axis events. Returns true if
+    /// the event came from a controller.
+    pub fn handle_event(&mut self, event: &Event, game_state: &mut GameState) -> bool {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                // `which` is a device index here; later events use the instance id
+                match self.subsystem.open(*which) {
+                    Ok(controller) => {
+                        println!("Controller connected: {}", controller.name());
+                        self.open.insert(controller.instance_id(), controller);
+                    },
+                    Err(e) => println!("Could not open controller {}: {}", which, e),
+                }
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.open.remove(which) {
+                    println!("Controller disconnected: {}", controller.name());
+                }
+                // Release only what this controller held
+                let (held, move_x) = self.inputs.remove(*which);
+                for button in held {
+                    game_state.pad_input(*which, &button, false);
+                }
+                game_state.input_axis(InputAxis::MoveX, move_x);
+            },
+            Event::ControllerButtonDown { which, button, .. } => {
+                let button = format!("Pad:{}", button.string());
+                self.inputs.button(*which, &button, true);
+                game_state.pad_input(*which, &button, true);
+            },
+            Event::ControllerButtonUp { which, button, .. } => {
+                let button = format!("Pad:{}", button.string());
+                self.inputs.button(*which, &button, false);
+                game_state.pad_input(*which, &button, false);
+            },
+            Event::ControllerAxisMotion { which, axis: Axis::LeftX, value, .. } => {
+                let deadzone = game_state.world.read_resource::<InputMap>().deadzone;
+                let value = apply_deadzone(*value as f32 / i16::MAX as f32, deadzone);
+                let move_x = self.inputs.set_move_x(*which, value);
+                game_state.input_axis(InputAxis::MoveX, move_x);
+            },
+            Event::ControllerAxisMotion { .. } => {},
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,599 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use specs::{World, WorldExt};
+
+use crate::components::DrawOrder;
+use crate::prefab::{spawn_components, ComponentDef, PrefabRegistry};
+use crate::resources::WorldBounds;
+use crate::utils::legend::{parse_color, TileOverrides};
+use crate::utils::level_loader::{parse_physics, spawns_player, LevelInfo};
+
+/// Prefab spawned for every tile of a tile layer without a `prefab` property.
+const DEFAULT_TILE_PREFAB: &str = "platform";
+
+/// Tiled stores flip and rotation flags in the top bits of each tile id.
+const GID_FLAGS: u32 = 0xf000_0000;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Io(io::Error),
+    /// The file is not valid Tiled JSON or TMX.
+    Parse(String),
+    /// The map uses a Tiled feature the importer does not handle.
+    Unsupported(String),
+    /// A layer or object refers to an unknown prefab or has a bad property.
+    Invalid(String),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiledError::Io(e) => write!(f, "could not read map: {}", e),
+            TiledError::Parse(message) => write!(f, "invalid Tiled map: {}", message),
+            TiledError::Unsupported(message) => write!(f, "unsupported Tiled feature: {}", message),
+            TiledError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TiledError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TiledError {
+    fn from(e: io::Error) -> Self {
+        TiledError::Io(e)
+    }
+}
+
+/// Reads a Tiled map, `.tmx` or `.json`/`.tmj`, and loads it into the world.
+/// The level name defaults to the file stem.
+pub fn load_tiled_file<P: AsRef<Path>>(world: &mut World, path: P) -> Result<LevelInfo, TiledError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let mut map = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tmx") => parse_tmx(&text)?,
+        _ => parse_json(&text)?,
+    };
+    if !map.properties.iter().any(|(key, _)| key == "name") {
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        map.properties.push(("name".to_string(), stem));
+    }
+    spawn_map(world, &map)
+}
+
+/// Loads a map saved in Tiled's JSON format.
+pub fn load_tiled_json(world: &mut World, text: &str) -> Result<LevelInfo, TiledError> {
+    spawn_map(world, &parse_json(text)?)
+}
+
+/// Loads a map saved in Tiled's TMX (XML) format.
+pub fn load_tiled_tmx(world: &mut World, text: &str) -> Result<LevelInfo, TiledError> {
+    spawn_map(world, &parse_tmx(text)?)
+}
+
+/// Format-independent view of the parts of a Tiled map the importer uses.
+/// Group layers are flattened into their children and hidden layers dropped.
+struct Map {
+    width: usize,
+    height: usize,
+    tile_width: f32,
+    tile_height: f32,
+    properties: Vec<(String, String)>,
+    layers: Vec<Layer>,
+}
+
+struct Layer {
+    name: String,
+    offset: (f32, f32),
+    properties: Vec<(String, String)>,
+    content: LayerContent,
+}
+
+enum LayerContent {
+    Tiles { width: usize, gids: Vec<u32> },
+    Objects(Vec<Object>),
+}
+
+struct Object {
+    id: u32,
+    name: String,
+    /// The object's class (Tiled 1.9+) or type.
+    class: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// Tile objects are anchored at their bottom-left corner.
+    is_tile: bool,
+    properties: Vec<(String, String)>,
+}
+
+/// Spawns every visible layer of the map, bottom layer first. Each layer's
+/// entities get a `DrawOrder` matching the layer's position among them. Nothing is
+/// spawned if any layer or object is invalid.
+fn spawn_map(world: &mut World, map: &Map) -> Result<LevelInfo, TiledError> {
+    let mut info = level_info(map)?;
+
+    let mut spawns: Vec<(Vec<ComponentDef>, (f32, f32), i32)> = Vec::new();
+    {
+        let prefabs = world.read_resource::<PrefabRegistry>();
+        for (index, layer) in map.layers.iter().enumerate() {
+            let draw_order = index as i32;
+            match &layer.content {
+                LayerContent::Tiles { width, gids } => {
+                    let (prefab, mut overrides) = layer_settings(&layer.properties, DEFAULT_TILE_PREFAB)
+                        .map_err(|message| TiledError::Invalid(format!("layer '{}': {}", layer.name, message)))?;
+                    overrides.width = overrides.width.or(Some(map.tile_width));
+                    overrides.height = overrides.height.or(Some(map.tile_height));
+                    let components = resolve(&prefabs, &prefab, &overrides)
+                        .map_err(|message| TiledError::Invalid(format!("layer '{}': {}", layer.name, message)))?;
+                    let (offset_x, offset_y) = overrides.offset.unwrap_or((0.0, 0.0));
+
+                    for (i, gid) in gids.iter().enumerate() {
+                        if *gid == 0 {
+                            continue;
+                        }
+                        let x = (i % width) as f32 * map.tile_width + layer.offset.0 + offset_x;
+                        let y = (i / width) as f32 * map.tile_height + layer.offset.1 + offset_y;
+                        spawns.push((components.clone(), (x, y), draw_order));
+                    }
+                },
+                LayerContent::Objects(objects) => {
+                    for object in objects {
+                        let context = |message: String| {
+                            TiledError::Invalid(format!("layer '{}', object {} ('{}'): {}", layer.name, object.id, object.name, message))
+                        };
+                        let default_prefab = if object.class.is_empty() { &object.name } else { &object.class };
+                        let (prefab, mut overrides) = layer_settings(&object.properties, default_prefab).map_err(context)?;
+                        if object.width > 0.0 && object.height > 0.0 {
+                            overrides.width = overrides.width.or(Some(object.width));
+                            overrides.height = overrides.height.or(Some(object.height));
+                        }
+                        let components = resolve(&prefabs, &prefab, &overrides).map_err(context)?;
+
+                        let (offset_x, offset_y) = overrides.offset.unwrap_or((0.0, 0.0));
+                        let top = if object.is_tile { object.y - object.height } else { object.y };
+                        let pos = (object.x + layer.offset.0 + offset_x, top + layer.offset.1 + offset_y);
+                        spawns.push((components, pos, draw_order));
+                    }
+                },
+            }
+        }
+    }
+
+    info.spawn = spawns.iter().find(|(components, _, _)| spawns_player(components)).map(|(_, pos, _)| *pos);
+    world.insert(WorldBounds::new(map.width as f32 * map.tile_width, map.height as f32 * map.tile_height));
+    world.insert(info.physics);
+    world.insert(info.clone());
+    for (components, pos, layer) in spawns {
+        let entity = spawn_components(world, &components, pos);
+        world.write_storage::<DrawOrder>().insert(entity, DrawOrder { layer }).ok();
+    }
+
+    Ok(info)
+}
+
+/// Reads the level header from the map's custom properties: `name`, `next`,
+/// `background`, `music` and the physics settings, as in a `.level` header.
+fn level_info(map: &Map) -> Result<LevelInfo, TiledError> {
+    let mut info = LevelInfo { tile_size: map.tile_width, ..LevelInfo::default() };
+    for (key, value) in &map.properties {
+        match key.as_str() {
+            "name" => info.name = value.clone(),
+            "next" => info.next = Some(value.clone()).filter(|next| !next.is_empty()),
+            "background" => {
+                info.background = parse_color(value)
+                    .ok_or_else(|| TiledError::Invalid(format!("map property 'background': invalid color '{}'", value)))?;
+            },
+            "music" => info.music = Some(value.clone()).filter(|music| !music.is_empty()),
+            "gravity" | "drag" | "rest_speed" | "kill_y" => {
+                parse_physics(&mut info.physics, key, value)
+                    .map_err(|message| TiledError::Invalid(format!("map property '{}': {}", key, message)))?;
+            },
+            // Other map properties are left for the game to read
+            _ => {},
+        }
+    }
+    Ok(info)
+}
+
+/// Splits custom properties into the prefab to spawn (the `prefab`
+/// property, or `default_prefab`) and legend-style overrides.
+fn layer_settings(properties: &[(String, String)], default_prefab: &str) -> Result<(String, TileOverrides), String> {
+    let mut prefab = default_prefab.to_string();
+    let mut overrides = TileOverrides::default();
+    for (key, value) in properties {
+        match key.as_str() {
+            "prefab" => prefab = value.clone(),
+            _ => overrides.set(key, value)?,
+        }
+    }
+    if prefab.is_empty() {
+        return Err("no prefab: set the object's class or a 'prefab' property".to_string());
+    }
+    Ok((prefab, overrides))
+}
+
+fn resolve(prefabs: &PrefabRegistry, prefab: &str, overrides: &TileOverrides) -> Result<Vec<ComponentDef>, String> {
+    let mut components = prefabs.resolve(prefab).map_err(|e| e.to_string())?;
+    overrides.apply(&mut components);
+    Ok(components)
+}
+
+/// Converts a Tiled `#aarrggbb` or `#rrggbb` color to the `r, g, b` form used
+/// by overrides. Other values are returned unchanged.
+fn normalize_color(value: &str) -> String {
+    let hex = match value.strip_prefix('#') {
+        Some(hex) if hex.len() == 8 => &hex[2..],
+        Some(hex) if hex.len() == 6 => hex,
+        _ => return value.to_string(),
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => format!("{}, {}, {}", r, g, b),
+        _ => value.to_string(),
+    }
+}
+
+fn check_map_header(orientation: &str, infinite: bool) -> Result<(), TiledError> {
+    if orientation != "orthogonal" {
+        return Err(TiledError::Unsupported(format!("{} orientation, only orthogonal maps are supported", orientation)));
+    }
+    if infinite {
+        return Err(TiledError::Unsupported("infinite maps".to_string()));
+    }
+    Ok(())
+}
+
+fn check_tiles(layer: &str, gids: &[u32], width: usize, height: usize) -> Result<(), TiledError> {
+    if gids.len() != width * height {
+        return Err(TiledError::Parse(format!("layer '{}' has {} tiles, expected {}x{}", layer, gids.len(), width, height)));
+    }
+    if gids.iter().any(|gid| gid & GID_FLAGS != 0) {
+        return Err(TiledError::Unsupported(format!("flipped or rotated tiles in layer '{}'", layer)));
+    }
+    Ok(())
+}
+
+// --- JSON -----------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default = "default_orientation")]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    data: Option<serde_json::Value>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    #[serde(default = "default_visible")]
+    visible: bool,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    gid: Option<u32>,
+    #[serde(default)]
+    ellipse: bool,
+    polygon: Option<serde_json::Value>,
+    polyline: Option<serde_json::Value>,
+    text: Option<serde_json::Value>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    value: serde_json::Value,
+}
+
+fn default_orientation() -> String {
+    "orthogonal".to_string()
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn parse_json(text: &str) -> Result<Map, TiledError> {
+    let json: JsonMap = serde_json::from_str(text).map_err(|e| TiledError::Parse(e.to_string()))?;
+    check_map_header(&json.orientation, json.infinite)?;
+
+    let mut layers = Vec::new();
+    flatten_json_layers(json.layers, (0.0, 0.0), &mut layers)?;
+    Ok(Map {
+        width: json.width,
+        height: json.height,
+        tile_width: json.tilewidth,
+        tile_height: json.tileheight,
+        properties: json_properties(json.properties),
+        layers,
+    })
+}
+
+fn flatten_json_layers(json_layers: Vec<JsonLayer>, parent_offset: (f32, f32), layers: &mut Vec<Layer>) -> Result<(), TiledError> {
+    for layer in json_layers {
+        // Hidden layers, and everything in a hidden group, are left out
+        if !layer.visible {
+            continue;
+        }
+        let offset = (parent_offset.0 + layer.offsetx, parent_offset.1 + layer.offsety);
+        let content = match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.encoding.as_deref().unwrap_or("csv") != "csv" || layer.compression.as_deref().is_some_and(|c| !c.is_empty()) {
+                    return Err(TiledError::Unsupported(format!("layer '{}' is encoded; save tile layers as CSV", layer.name)));
+                }
+                let gids: Vec<u32> = match layer.data {
+                    Some(data) => serde_json::from_value(data)
+                        .map_err(|e| TiledError::Parse(format!("layer '{}': {}", layer.name, e)))?,
+                    None => Vec::new(),
+                };
+                check_tiles(&layer.name, &gids, layer.width, layer.height)?;
+                LayerContent::Tiles { width: layer.width, gids }
+            },
+            "objectgroup" => {
+                let objects = layer.objects.into_iter()
+                    .map(|object| json_object(&layer.name, object))
+                    .collect::<Result<_, _>>()?;
+                LayerContent::Objects(objects)
+            },
+            "group" => {
+                flatten_json_layers(layer.layers, offset, layers)?;
+                continue;
+            },
+            other => return Err(TiledError::Unsupported(format!("{} '{}'", other, layer.name))),
+        };
+        layers.push(Layer { name: layer.name, offset, properties: json_properties(layer.properties), content });
+    }
+    Ok(())
+}
+
+fn json_object(layer: &str, object: JsonObject) -> Result<Object, TiledError> {
+    let shape = if object.ellipse {
+        Some("ellipse")
+    } else if object.polygon.is_some() {
+        Some("polygon")
+    } else if object.polyline.is_some() {
+        Some("polyline")
+    } else if object.text.is_some() {
+        Some("text")
+    } else {
+        None
+    };
+    check_object(layer, object.id, shape, object.rotation, object.gid)?;
+
+    Ok(Object {
+        id: object.id,
+        name: object.name,
+        class: if object.class.is_empty() { object.kind } else { object.class },
+        x: object.x,
+        y: object.y,
+        width: object.width,
+        height: object.height,
+        is_tile: object.gid.is_some(),
+        properties: json_properties(object.properties),
+    })
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> Vec<(String, String)> {
+    properties.into_iter()
+        .map(|property| {
+            let value = match property.value {
+                serde_json::Value::String(text) if property.kind == "color" => normalize_color(&text),
+                serde_json::Value::String(text) => text,
+                other => other.to_string(),
+            };
+            (property.name, value)
+        })
+        .collect()
+}
+
+fn check_object(layer: &str, id: u32, shape: Option<&str>, rotation: f32, gid: Option<u32>) -> Result<(), TiledError> {
+    if let Some(shape) = shape {
+        return Err(TiledError::Unsupported(format!("{} object {} in layer '{}'", shape, id, layer)));
+    }
+    if rotation != 0.0 {
+        return Err(TiledError::Unsupported(format!("rotated object {} in layer '{}'", id, layer)));
+    }
+    if gid.is_some_and(|gid| gid & GID_FLAGS != 0) {
+        return Err(TiledError::Unsupported(format!("flipped tile object {} in layer '{}'", id, layer)));
+    }
+    Ok(())
+}
+
+// --- TMX ------------------------------------------------------------------
+
+fn parse_tmx(text: &str) -> Result<Map, TiledError> {
+    let document = roxmltree::Document::parse(text).map_err(|e| TiledError::Parse(e.to_string()))?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err(TiledError::Parse(format!("expected <map>, found <{}>", root.tag_name().name())));
+    }
+    check_map_header(root.attribute("orientation").unwrap_or("orthogonal"), root.attribute("infinite") == Some("1"))?;
+
+    let mut layers = Vec::new();
+    flatten_tmx_layers(root, (0.0, 0.0), &mut layers)?;
+    Ok(Map {
+        width: attribute(root, "width")?,
+        height: attribute(root, "height")?,
+        tile_width: attribute(root, "tilewidth")?,
+        tile_height: attribute(root, "tileheight")?,
+        properties: tmx_properties(root),
+        layers,
+    })
+}
+
+fn flatten_tmx_layers(parent: roxmltree::Node, parent_offset: (f32, f32), layers: &mut Vec<Layer>) -> Result<(), TiledError> {
+    for node in parent.children().filter(|node| node.is_element()) {
+        // Hidden layers, and everything in a hidden group, are left out
+        if node.attribute("visible") == Some("0") {
+            continue;
+        }
+        let name = node.attribute("name").unwrap_or_default().to_string();
+        let offset = (
+            parent_offset.0 + optional_attribute(node, "offsetx")?.unwrap_or(0.0),
+            parent_offset.1 + optional_attribute(node, "offsety")?.unwrap_or(0.0),
+        );
+        let content = match node.tag_name().name() {
+            "layer" => {
+                let width = attribute(node, "width")?;
+                let height = attribute(node, "height")?;
+                let gids = tmx_tile_data(node, &name)?;
+                check_tiles(&name, &gids, width, height)?;
+                LayerContent::Tiles { width, gids }
+            },
+            "objectgroup" => {
+                let objects = node.children()
+                    .filter(|child| child.has_tag_name("object"))
+                    .map(|object| tmx_object(&name, object))
+                    .collect::<Result<_, _>>()?;
+                LayerContent::Objects(objects)
+            },
+            "group" => {
+                flatten_tmx_layers(node, offset, layers)?;
+                continue;
+            },
+            "imagelayer" => return Err(TiledError::Unsupported(format!("image layer '{}'", name))),
+            // Tilesets, properties and editor settings carry no entities
+            _ => continue,
+        };
+        layers.push(Layer { name, offset, properties: tmx_properties(node), content });
+    }
+    Ok(())
+}
+
+fn tmx_tile_data(layer: roxmltree::Node, name: &str) -> Result<Vec<u32>, TiledError> {
+    let data = match layer.children().find(|child| child.has_tag_name("data")) {
+        Some(data) => data,
+        None => return Ok(Vec::new()),
+    };
+    if data.attribute("compression").is_some() || data.children().any(|child| child.has_tag_name("chunk")) {
+        return Err(TiledError::Unsupported(format!("layer '{}' is compressed or chunked; save tile layers as CSV", name)));
+    }
+    let invalid = |value: &str| TiledError::Parse(format!("layer '{}': invalid tile id '{}'", name, value));
+    match data.attribute("encoding") {
+        Some("csv") => data.text().unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.parse().map_err(|_| invalid(value)))
+            .collect(),
+        // Tiled's old XML format: one <tile gid="..."/> per cell
+        None => data.children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| {
+                let gid = tile.attribute("gid").unwrap_or("0");
+                gid.parse().map_err(|_| invalid(gid))
+            })
+            .collect(),
+        Some(encoding) => Err(TiledError::Unsupported(format!("{} encoding in layer '{}'; save tile layers as CSV", encoding, name))),
+    }
+}
+
+fn tmx_object(layer: &str, node: roxmltree::Node) -> Result<Object, TiledError> {
+    let id = optional_attribute(node, "id")?.unwrap_or(0);
+    let shape = ["ellipse", "polygon", "polyline", "text"].into_iter()
+        .find(|shape| node.children().any(|child| child.has_tag_name(*shape)));
+    let gid = optional_attribute(node, "gid")?;
+    check_object(layer, id, shape, optional_attribute(node, "rotation")?.unwrap_or(0.0), gid)?;
+
+    Ok(Object {
+        id,
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        class: node.attribute("class").or_else(|| node.attribute("type")).unwrap_or_default().to_string(),
+        x: attribute(node, "x")?,
+        y: attribute(node, "y")?,
+        width: optional_attribute(node, "width")?.unwrap_or(0.0),
+        height: optional_attribute(node, "height")?.unwrap_or(0.0),
+        is_tile: gid.is_some(),
+        properties: tmx_properties(node),
+    })
+}
+
+fn tmx_properties(node: roxmltree::Node) -> Vec<(String, String)> {
+    let mut properties = Vec::new();
+    let list = node.children().find(|child| child.has_tag_name("properties"));
+    for property in list.iter().flat_map(|list| list.children()).filter(|child| child.has_tag_name("property")) {
+        let name = property.attribute("name").unwrap_or_default().to_string();
+        // Multi-line string values are stored as the element's text
+        let value = property.attribute("value").or_else(|| property.text()).unwrap_or_default();
+        let value = match property.attribute("type") {
+            Some("color") => normalize_color(value),
+            _ => value.to_string(),
+        };
+        properties.push((name, value));
+    }
+    properties
+}
+
+fn attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T, TiledError> {
+    optional_attribute(node, name)?
+        .ok_or_else(|| TiledError::Parse(format!("<{}> is missing '{}'", node.tag_name().name(), name)))
+}
+
+fn optional_attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<Option<T>, TiledError> {
+    node.attribute(name)
+        .map(|value| value.parse().map_err(|_| {
+            TiledError::Parse(format!("<{}> has invalid '{}' value '{}'", node.tag_name().name(), name, value))
+        }))
+        .transpose()
+}
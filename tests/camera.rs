@@ -0,0 +1,113 @@
+use rust_2d_engine::components::{Player, Position, Velocity};
+use rust_2d_engine::resources::{Camera, WorldBounds};
+use rust_2d_engine::GameState;
+use specs::{Join, WorldExt};
+
+/// A 40 by 10 tile level (1600x400) with the player standing on the floor
+/// at `player_column`.
+fn level(player_column: usize) -> String {
+    let mut rows = vec![String::new(); 8];
+    rows.push(format!("{}P", " ".repeat(player_column)));
+    rows.push("#".repeat(40));
+    rows.join("\n")
+}
+
+fn state(level: &str) -> GameState {
+    let mut state = GameState::builder().with_viewport(200.0, 200.0).build();
+    state.load_level(level).unwrap();
+    state
+}
+
+fn camera(state: &GameState) -> (f32, f32) {
+    let camera = state.world.read_resource::<Camera>();
+    (camera.x, camera.y)
+}
+
+fn player_center(state: &GameState) -> (f32, f32) {
+    let positions = state.world.read_storage::<Position>();
+    let players = state.world.read_storage::<Player>();
+    let (pos, _) = (&positions, &players).join().next().unwrap();
+    (pos.x + 20.0, pos.y + 20.0)
+}
+
+fn move_player(state: &mut GameState, dx: f32) {
+    let mut positions = state.world.write_storage::<Position>();
+    let players = state.world.read_storage::<Player>();
+    for (pos, _) in (&mut positions, &players).join() {
+        pos.x += dx;
+    }
+}
+
+#[test]
+fn the_camera_snaps_to_the_player_after_a_level_load() {
+    let mut state = state(&level(20));
+    state.step();
+    let (x, _) = camera(&state);
+    assert_eq!(x + 100.0, player_center(&state).0);
+
+    // A new level jumps straight to the new player rather than easing over
+    state.load_level(&level(2)).unwrap();
+    state.step();
+    assert_eq!(camera(&state).0, 0.0);
+    state.load_level(&level(20)).unwrap();
+    state.step();
+    assert_eq!(camera(&state).0 + 100.0, player_center(&state).0);
+}
+
+#[test]
+fn small_moves_inside_the_dead_zone_do_not_scroll() {
+    let mut state = state(&level(20));
+    state.step();
+    let start = camera(&state);
+
+    // The dead zone is 80px wide, so 30px either side of center is ignored
+    move_player(&mut state, 30.0);
+    state.step();
+    assert_eq!(camera(&state), start);
+    move_player(&mut state, -60.0);
+    state.step();
+    assert_eq!(camera(&state), start);
+
+    // Leaving it starts the camera easing after the player
+    move_player(&mut state, 150.0);
+    state.step();
+    let (x, _) = camera(&state);
+    assert!(x > start.0, "camera at {} did not follow from {}", x, start.0);
+    assert!(x + 100.0 < player_center(&state).0, "camera should ease, not jump");
+}
+
+#[test]
+fn the_camera_looks_ahead_of_a_moving_player() {
+    let mut state = state(&level(20));
+    {
+        let mut velocities = state.world.write_storage::<Velocity>();
+        let players = state.world.read_storage::<Player>();
+        for (vel, _) in (&mut velocities, &players).join() {
+            vel.x = 150.0;
+        }
+    }
+    state.step();
+    let look_ahead = state.world.read_resource::<Camera>().look_ahead;
+    assert_eq!(camera(&state).0 + 100.0, player_center(&state).0 + look_ahead);
+}
+
+#[test]
+fn the_view_is_clamped_to_the_level_bounds() {
+    let view = Camera::new(200.0, 200.0);
+    let bounds = WorldBounds::new(1000.0, 800.0);
+    assert_eq!(view.clamp_to(-50.0, -10.0, &bounds), (0.0, 0.0));
+    assert_eq!(view.clamp_to(900.0, 700.0, &bounds), (800.0, 600.0));
+    assert_eq!(view.clamp_to(300.0, 200.0, &bounds), (300.0, 200.0));
+
+    // A level smaller than the view stays pinned to its top-left corner
+    let small = WorldBounds::new(120.0, 80.0);
+    assert_eq!(view.clamp_to(50.0, 30.0, &small), (0.0, 0.0));
+    assert_eq!(view.clamp_to(-50.0, -30.0, &small), (0.0, 0.0));
+
+    // Stepping a real level keeps the view inside it at the edges
+    let mut state = state(&level(2));
+    state.step();
+    let (x, y) = camera(&state);
+    assert_eq!(x, 0.0);
+    assert_eq!(y, 200.0);
+}
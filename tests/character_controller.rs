@@ -0,0 +1,125 @@
+mod common;
+
+use rust_2d_engine::components::{CharacterController, ControllerTuning};
+use rust_2d_engine::prefab::{ComponentDef, PrefabRegistry};
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::GameState;
+use common::player;
+use specs::{Join, WorldExt};
+
+/// Highest point (smallest y) reached when jump is held for `held` steps.
+fn jump_peak(held: u32) -> f32 {
+    let mut state = GameState::new();
+    state.load_level("\n\n\n\n\nP\n#").unwrap();
+    state.step();
+    state.input(InputAction::Jump, true);
+    let mut peak = player(&state).pos.y;
+    for frame in 0..90 {
+        if frame == held {
+            state.input(InputAction::Jump, false);
+        }
+        state.step();
+        peak = peak.min(player(&state).pos.y);
+    }
+    peak
+}
+
+#[test]
+fn releasing_jump_early_cuts_it_short() {
+    let tap = jump_peak(1);
+    let hold = jump_peak(60);
+    // A held 600px/s jump under 1500px/s² gravity rises 120px
+    assert!((hold - (200.0 - 120.0)).abs() < 10.0, "held jump peaked at {}", hold);
+    assert!(tap > hold + 40.0, "tap peaked at {}, hold at {}", tap, hold);
+}
+
+/// Walks right off a one-tile ledge and presses jump `late` steps after
+/// leaving it. Returns whether the player jumped.
+fn jump_after_leaving_ledge(late: u32) -> bool {
+    let mut state = GameState::new();
+    state.load_level("\n\nP      ?\n#\n\n\n\n").unwrap();
+    state.step();
+    state.input(InputAction::MoveRight, true);
+    while player(&state).grounded {
+        state.step();
+    }
+    for _ in 0..late {
+        state.step();
+    }
+    state.input(InputAction::Jump, true);
+    state.step();
+    player(&state).vel.y < 0.0
+}
+
+#[test]
+fn coyote_time_allows_late_jumps() {
+    // The default coyote time is 0.1s, six steps
+    assert!(jump_after_leaving_ledge(2));
+    assert!(!jump_after_leaving_ledge(12));
+}
+
+/// Drops the player onto the floor and presses jump `early` steps before it
+/// lands. Returns whether the player jumped on landing.
+fn jump_before_landing(early: u32) -> bool {
+    let mut state = GameState::new();
+    state.load_level("P\n\n\n\n\n\n#").unwrap();
+    let mut fall = 0;
+    while !player(&state).grounded {
+        state.step();
+        fall += 1;
+    }
+
+    let mut state = GameState::new();
+    state.load_level("P\n\n\n\n\n\n#").unwrap();
+    for _ in 0..fall - early {
+        state.step();
+    }
+    state.input(InputAction::Jump, true);
+    state.step();
+    state.input(InputAction::Jump, false);
+    (0..early + 2).any(|_| {
+        state.step();
+        player(&state).vel.y < 0.0
+    })
+}
+
+#[test]
+fn jumps_pressed_just_before_landing_are_buffered() {
+    // The default buffer is 0.1s, six steps
+    assert!(jump_before_landing(3));
+    assert!(!jump_before_landing(15));
+}
+
+#[test]
+fn falls_are_capped_at_max_fall_speed() {
+    let mut state = GameState::new();
+    state.load_level("P\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n#").unwrap();
+    let max_fall = ControllerTuning::default().max_fall_speed;
+    let mut fastest: f32 = 0.0;
+    while !player(&state).grounded {
+        state.step();
+        fastest = fastest.max(player(&state).vel.y);
+    }
+    assert_eq!(fastest, max_fall);
+}
+
+#[test]
+fn prefabs_tune_the_controller() {
+    let mut registry = PrefabRegistry::default();
+    registry.extend(PrefabRegistry::parse_ron(r#"{
+        "floaty": (parent: "player", components: [
+            CharacterController(coyote_time: 0.25, max_fall_speed: 800.0),
+        ]),
+    }"#).unwrap());
+    let components = registry.resolve("floaty").unwrap();
+    let tuning = components.iter().find_map(|component| match component {
+        ComponentDef::CharacterController(tuning) => Some(*tuning),
+        _ => None,
+    }).unwrap();
+    assert_eq!(tuning, ControllerTuning { coyote_time: 0.25, max_fall_speed: 800.0, ..ControllerTuning::default() });
+
+    let mut state = GameState::builder().with_prefabs(registry).build();
+    state.load_level("tile: F = floaty\n---\nF\n#").unwrap();
+    let controllers = state.world.read_storage::<CharacterController>();
+    assert_eq!(controllers.join().next().unwrap().tuning.max_fall_speed, 800.0);
+}
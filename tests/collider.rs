@@ -0,0 +1,104 @@
+use rust_2d_engine::components::{Collectible, Health, Player, Position, Velocity};
+use rust_2d_engine::geometry::{shape_contact, shapes_overlap, Shape};
+use rust_2d_engine::{spawn_prefab, GameState, PrefabRegistry};
+use specs::{Join, WorldExt};
+
+/// An enemy whose 10x10 hitbox sits in the middle of its 40x40 sprite.
+const GHOST: &str = r#"{
+    "ghost": (parent: "enemy", components: [
+        Velocity(x: 0.0, y: 0.0),
+        Collider(shape: Aabb(width: 10.0, height: 10.0), offset: (15.0, 15.0)),
+    ]),
+}"#;
+
+fn move_to(state: &mut GameState, entity: specs::Entity, x: f32, y: f32) {
+    *state.world.write_storage::<Position>().get_mut(entity).unwrap() = Position { x, y };
+}
+
+#[test]
+fn circles_only_touch_inside_their_radius() {
+    let circle = Shape::Circle { x: 50.0, y: 50.0, radius: 10.0 };
+
+    // Bounding boxes overlap at the corner, the circle does not reach
+    assert_eq!(shape_contact(circle, Shape::Rect((0.0, 0.0, 42.0, 42.0))), None);
+    let (normal, penetration) = shape_contact(circle, Shape::Rect((0.0, 0.0, 45.0, 100.0))).unwrap();
+    assert_eq!(normal, (1.0, 0.0));
+    assert!((penetration - 5.0).abs() < 1e-5);
+
+    // Normals point towards the first shape whichever way round
+    let (normal, _) = shape_contact(Shape::Rect((0.0, 0.0, 45.0, 100.0)), circle).unwrap();
+    assert_eq!(normal, (-1.0, 0.0));
+
+    let other = Shape::Circle { x: 70.0, y: 50.0, radius: 10.0 };
+    assert_eq!(shape_contact(circle, other), Some(((-1.0, 0.0), 0.0)));
+    assert!(!shapes_overlap(circle, other));
+}
+
+#[test]
+fn coins_are_collected_by_their_circle() {
+    let mut state = GameState::new();
+    state.load_level("P\n###").unwrap();
+    // The coin's 20x20 box overlaps the player's corner, its circle does not
+    let coin = spawn_prefab(&mut state.world, "coin", (38.0, -18.0)).unwrap();
+    state.step();
+    assert!(state.world.read_storage::<Collectible>().contains(coin));
+
+    move_to(&mut state, coin, 30.0, 0.0);
+    state.step();
+    state.world.maintain();
+    assert!(!state.world.is_alive(coin));
+    let players = state.world.read_storage::<Player>();
+    assert_eq!((&players).join().next().unwrap().score, 10);
+}
+
+#[test]
+fn hitboxes_smaller_than_sprites() {
+    let mut prefabs = PrefabRegistry::default();
+    prefabs.extend(PrefabRegistry::parse_ron(GHOST).unwrap());
+    let mut state = GameState::builder().with_prefabs(prefabs).build();
+    state.load_level("P\n####").unwrap();
+    let ghost = spawn_prefab(&mut state.world, "ghost", (30.0, 0.0)).unwrap();
+
+    // The sprites overlap but the hitboxes do not; the ghost lands on its hitbox
+    for _ in 0..30 {
+        state.step();
+    }
+    let health = |state: &GameState| {
+        let players = state.world.read_storage::<Player>();
+        let healths = state.world.read_storage::<Health>();
+        (&players, &healths).join().next().unwrap().1.current
+    };
+    assert_eq!(health(&state), 100);
+    assert_eq!(state.world.read_storage::<Position>().get(ghost).unwrap().y, 40.0 - 25.0);
+
+    move_to(&mut state, ghost, 10.0, 15.0);
+    state.step();
+    assert_eq!(health(&state), 80);
+}
+
+#[test]
+fn world_bounds_stop_the_hitbox_not_the_sprite() {
+    // Floats left and right across a level three tiles wide
+    let drifter = r#"{
+        "drifter": (components: [
+            Renderable(width: 40.0, height: 40.0, color: (0, 0, 255)),
+            Velocity(x: -300.0, y: 0.0),
+            Collider(shape: Aabb(width: 10.0, height: 10.0), offset: (15.0, 15.0)),
+        ]),
+    }"#;
+    let mut prefabs = PrefabRegistry::default();
+    prefabs.extend(PrefabRegistry::parse_ron(drifter).unwrap());
+    let mut state = GameState::builder().with_prefabs(prefabs).build();
+    state.load_level("\n\n###").unwrap();
+    let body = spawn_prefab(&mut state.world, "drifter", (40.0, 0.0)).unwrap();
+    for _ in 0..30 {
+        state.step();
+    }
+    assert_eq!(state.world.read_storage::<Position>().get(body).unwrap().x, -15.0);
+
+    state.world.write_storage::<Velocity>().get_mut(body).unwrap().x = 300.0;
+    for _ in 0..30 {
+        state.step();
+    }
+    assert_eq!(state.world.read_storage::<Position>().get(body).unwrap().x, 120.0 - 25.0);
+}
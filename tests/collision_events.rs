@@ -0,0 +1,99 @@
+use rust_2d_engine::components::{Collectible, Platform, Player, Position};
+use rust_2d_engine::events::CollisionEvent;
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::{spawn_prefab, GameState, HeadlessRunner, InputScript, PrefabRegistry};
+use specs::shrev::EventChannel;
+use specs::{Join, WorldExt};
+
+const LEVEL: &str = "
+######
+#    #
+# P C#
+######
+";
+
+#[test]
+fn resting_on_a_platform_starts_one_contact() {
+    let mut state = GameState::new();
+    state.load_level(LEVEL).unwrap();
+    let mut reader = state.world.write_resource::<EventChannel<CollisionEvent>>().register_reader();
+    let mut runner = HeadlessRunner::new(state);
+    runner.run(60);
+
+    let world = &runner.state.world;
+    let players = world.read_storage::<Player>();
+    let platforms = world.read_storage::<Platform>();
+    let channel = world.read_resource::<EventChannel<CollisionEvent>>();
+    let floor_contacts: Vec<_> = channel.read(&mut reader)
+        .filter_map(|event| match event {
+            CollisionEvent::Started(contact) if players.contains(contact.a) && platforms.contains(contact.b) => Some(*contact),
+            _ => None,
+        })
+        .collect();
+
+    // Landing on the floor tile below starts exactly one contact, which
+    // lasts while the player rests on it
+    let below: Vec<_> = floor_contacts.iter().filter(|contact| contact.normal == (0.0, -1.0)).collect();
+    assert_eq!(below.len(), 1, "{:?}", floor_contacts);
+    assert!(below[0].penetration >= 0.0);
+    let mut tiles: Vec<_> = floor_contacts.iter().map(|contact| contact.b).collect();
+    tiles.sort();
+    tiles.dedup();
+    assert_eq!(tiles.len(), floor_contacts.len(), "contacts restarted: {:?}", floor_contacts);
+}
+
+#[test]
+fn collectibles_are_scored_from_collision_events() {
+    let mut state = GameState::new();
+    state.load_level(LEVEL).unwrap();
+    let script = InputScript::new().hold(InputAction::MoveRight, 0, 60);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    runner.run(60);
+
+    let world = &runner.state.world;
+    assert_eq!(world.read_storage::<Collectible>().join().count(), 0);
+    let players = world.read_storage::<Player>();
+    assert_eq!((&players).join().next().unwrap().score, 10);
+}
+
+#[test]
+fn collectibles_that_move_are_scored_whichever_entity_comes_first() {
+    // A drifting coin created before the player is the first of the pair
+    let mut prefabs = PrefabRegistry::default();
+    prefabs.extend(PrefabRegistry::parse_ron(r#"{
+        "drifting_coin": (parent: "coin", components: [
+            Velocity(x: -60.0, y: 0.0),
+            Collider(shape: Circle(radius: 10.0), triggers: ["player"]),
+        ]),
+    }"#).unwrap());
+    let mut state = GameState::builder().with_prefabs(prefabs).build();
+    state.load_level("\n\n\n######").unwrap();
+    let coin = spawn_prefab(&mut state.world, "drifting_coin", (150.0, 100.0)).unwrap();
+    let player = spawn_prefab(&mut state.world, "player", (0.0, 80.0)).unwrap();
+    assert!(coin < player);
+
+    for _ in 0..120 {
+        state.step();
+    }
+    state.world.maintain();
+    assert!(!state.world.is_alive(coin));
+    assert_eq!(state.world.read_storage::<Player>().get(player).unwrap().score, 10);
+}
+
+#[test]
+fn touching_a_trigger_edge_on_does_not_collect_it() {
+    let mut state = GameState::new();
+    state.load_level("P\n##").unwrap();
+    // The coin's circle touches the player's right side without overlapping
+    let coin = spawn_prefab(&mut state.world, "coin", (40.0, 10.0)).unwrap();
+    for _ in 0..30 {
+        state.step();
+    }
+    state.world.maintain();
+    assert!(state.world.is_alive(coin));
+
+    state.world.write_storage::<Position>().get_mut(coin).unwrap().x = 39.0;
+    state.step();
+    state.world.maintain();
+    assert!(!state.world.is_alive(coin));
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests. Each test crate compiles this
+//! module on its own and uses only part of it.
+#![allow(dead_code)]
+
+use rust_2d_engine::components::{Grounded, OnSlope, Player, Position, Velocity};
+use rust_2d_engine::{GameState, PrefabRegistry};
+use specs::{Join, WorldExt};
+
+/// The player's state at one point of a test.
+#[derive(Debug)]
+pub struct PlayerSnapshot {
+    pub pos: Position,
+    pub vel: Velocity,
+    pub grounded: bool,
+    /// Gradient of the slope the player stands on, if any.
+    pub slope: Option<f32>,
+}
+
+/// Looks up the first player in the world.
+pub fn player(state: &GameState) -> PlayerSnapshot {
+    let players = state.world.read_storage::<Player>();
+    let positions = state.world.read_storage::<Position>();
+    let velocities = state.world.read_storage::<Velocity>();
+    let grounded = state.world.read_storage::<Grounded>();
+    let on_slopes = state.world.read_storage::<OnSlope>();
+    let (entity, _, pos, vel) = (&state.world.entities(), &players, &positions, &velocities).join().next().unwrap();
+    PlayerSnapshot {
+        pos: Position { x: pos.x, y: pos.y },
+        vel: Velocity { x: vel.x, y: vel.y },
+        grounded: grounded.contains(entity),
+        slope: on_slopes.get(entity).map(|slope| slope.gradient),
+    }
+}
+
+/// A game whose registry has the built-in prefabs plus those in `ron`.
+pub fn state_with_prefabs(ron: &str) -> GameState {
+    let mut prefabs = PrefabRegistry::default();
+    prefabs.extend(PrefabRegistry::parse_ron(ron).unwrap());
+    GameState::builder().with_prefabs(prefabs).build()
+}
@@ -0,0 +1,49 @@
+use rust_2d_engine::components::{ParticleEmitter, Position};
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::{GameMode, GameState, HeadlessRunner, InputScript};
+use specs::{Builder, WorldExt};
+
+const LEVEL: &str = "
+####################
+#                  #
+#   C   C   C      #
+#  ### ### ###     #
+#                  #
+#      E           #
+#    #####         #
+#            C     #
+#   P      #####   #
+####################
+";
+
+fn run(seed: u64, frames: u64) -> u64 {
+    let mut state = GameState::builder()
+        .with_seed(seed)
+        .with_mode(GameMode::Playing)
+        .build();
+    state.load_level(LEVEL).unwrap();
+    state.world.create_entity()
+        .with(Position { x: 400.0, y: 200.0 })
+        .with(ParticleEmitter { rate: 0.5, lifetime: 1.0, color: (255, 255, 255) })
+        .build();
+
+    let script = InputScript::new()
+        .hold(InputAction::MoveRight, 10, 120)
+        .press(60, InputAction::Jump)
+        .release(62, InputAction::Jump)
+        .hold(InputAction::MoveLeft, 150, 240);
+
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    runner.run(frames);
+    runner.state.state_hash()
+}
+
+#[test]
+fn same_seed_and_input_produce_identical_state() {
+    assert_eq!(run(42, 300), run(42, 300));
+}
+
+#[test]
+fn different_seeds_diverge() {
+    assert_ne!(run(1, 300), run(2, 300));
+}
@@ -0,0 +1,57 @@
+use rust_2d_engine::components::{Damage, Dead, Health, Invulnerable, Player, Velocity};
+use rust_2d_engine::resources::CombatSettings;
+use rust_2d_engine::{GameMode, GameState, HeadlessRunner};
+use specs::{Join, WorldExt};
+
+const LEVEL: &str = "
+##########
+#        #
+#EP      #
+##########
+";
+
+fn runner(combat: CombatSettings) -> HeadlessRunner {
+    let mut state = GameState::builder()
+        .with_mode(GameMode::Playing)
+        .with_combat_settings(combat)
+        .build();
+    state.load_level(LEVEL).unwrap();
+    HeadlessRunner::new(state)
+}
+
+fn player_health(state: &GameState) -> i32 {
+    let players = state.world.read_storage::<Player>();
+    let healths = state.world.read_storage::<Health>();
+    (&players, &healths).join().next().unwrap().1.current
+}
+
+#[test]
+fn enemy_contact_damages_and_knocks_back_once_per_window() {
+    let mut runner = runner(CombatSettings::default());
+    runner.run(3);
+
+    assert_eq!(player_health(&runner.state), 80);
+    {
+        let players = runner.state.world.read_storage::<Player>();
+        let velocities = runner.state.world.read_storage::<Velocity>();
+        let invulnerable = runner.state.world.read_storage::<Invulnerable>();
+        let (_, vel, _) = (&players, &velocities, &invulnerable).join().next().unwrap();
+        assert!(vel.x > 0.0 && vel.y < 0.0, "knocked away from the enemy, got {:?}", vel);
+    }
+
+    // Still invulnerable a few steps later, even if the enemy catches up
+    runner.run(10);
+    assert_eq!(player_health(&runner.state), 80);
+}
+
+#[test]
+fn running_out_of_health_ends_the_game() {
+    let mut runner = runner(CombatSettings { contact_damage: 100, ..CombatSettings::default() });
+    runner.state.world.write_storage::<Damage>().clear();
+    runner.run(3);
+
+    assert_eq!(player_health(&runner.state), 0);
+    assert_eq!(runner.state.world.read_storage::<Dead>().join().count(), 1);
+    assert!(runner.state.player_dead());
+    assert_eq!(runner.state.mode, GameMode::GameOver);
+}
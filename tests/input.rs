@@ -0,0 +1,144 @@
+use rust_2d_engine::components::{Player, Velocity};
+use rust_2d_engine::utils::{apply_deadzone, ActionState, HeldBindings, InputAction, InputAxis, InputMap, PadInputs};
+use rust_2d_engine::GameState;
+use specs::{Join, WorldExt};
+
+const LEVEL: &str = "
+ P
+##########
+";
+
+fn key(state: &mut GameState, key: &str, pressed: bool) {
+    state.key_input(key, pressed);
+    state.step();
+}
+
+fn jump_held(state: &GameState) -> bool {
+    state.world.read_resource::<ActionState>().pressed(InputAction::Jump)
+}
+
+#[test]
+fn actions_stay_held_until_every_bound_key_is_released() {
+    let mut map = InputMap::default();
+    map.bind("W", InputAction::Jump);
+    let mut state = GameState::builder().with_input_map(map).build();
+
+    // Hold Space, then tap W while it is still down
+    key(&mut state, "Space", true);
+    assert!(jump_held(&state));
+    key(&mut state, "W", true);
+    key(&mut state, "W", false);
+    assert!(jump_held(&state));
+
+    key(&mut state, "Space", false);
+    assert!(!jump_held(&state));
+}
+
+#[test]
+fn repeated_presses_of_a_held_key_do_not_stack() {
+    let mut state = GameState::new();
+    key(&mut state, "Space", true);
+    key(&mut state, "Space", true);
+    key(&mut state, "Space", false);
+    assert!(!jump_held(&state));
+
+    let mut held = HeldBindings::default();
+    assert!(held.press("Space", InputAction::Jump));
+    assert!(!held.press("Space", InputAction::Jump));
+    assert!(!held.press("W", InputAction::Jump));
+    assert_eq!(held.release("Space"), None);
+    assert_eq!(held.release("W"), Some(InputAction::Jump));
+    assert_eq!(held.release("W"), None);
+}
+
+#[test]
+fn loading_a_level_forgets_held_keys() {
+    let mut state = GameState::new();
+    key(&mut state, "Space", true);
+    state.load_level(LEVEL).unwrap();
+    assert!(!jump_held(&state));
+
+    // The release of the key held before the load is harmless, and the key
+    // works normally afterwards
+    key(&mut state, "Space", false);
+    key(&mut state, "Space", true);
+    assert!(jump_held(&state));
+    key(&mut state, "Space", false);
+    assert!(!jump_held(&state));
+}
+
+#[test]
+fn unbound_keys_are_ignored() {
+    let mut state = GameState::new();
+    key(&mut state, "Q", true);
+    key(&mut state, "Q", false);
+    let actions = state.world.read_resource::<ActionState>();
+    assert!(InputAction::ALL.iter().all(|action| !actions.pressed(*action)));
+}
+
+#[test]
+fn the_deadzone_reads_as_zero_and_the_rest_is_rescaled() {
+    assert_eq!(apply_deadzone(0.1, 0.2), 0.0);
+    assert_eq!(apply_deadzone(-0.2, 0.2), 0.0);
+    assert!((apply_deadzone(0.6, 0.2) - 0.5).abs() < 1e-6);
+    assert!((apply_deadzone(-0.6, 0.2) + 0.5).abs() < 1e-6);
+    assert_eq!(apply_deadzone(1.0, 0.2), 1.0);
+    assert_eq!(apply_deadzone(-1.5, 0.2), -1.0);
+    assert_eq!(apply_deadzone(0.9, 1.0), 0.0);
+}
+
+/// Horizontal speed once the player has had time to get up to speed.
+fn speed_after_holding(move_x: f32) -> (f32, f32) {
+    let mut state = GameState::new();
+    state.load_level(LEVEL).unwrap();
+    state.input_axis(InputAxis::MoveX, move_x);
+    for _ in 0..20 {
+        state.step();
+    }
+
+    let players = state.world.read_storage::<Player>();
+    let velocities = state.world.read_storage::<Velocity>();
+    let (player, vel) = (&players, &velocities).join().next().unwrap();
+    (vel.x, player.speed)
+}
+
+#[test]
+fn analog_movement_scales_the_player_speed() {
+    let (full, speed) = speed_after_holding(1.0);
+    let (half, _) = speed_after_holding(0.5);
+    assert!(full > 0.0 && full <= speed);
+    assert!((half - full * 0.5).abs() < 1e-3, "half stick {} vs full stick {}", half, full);
+    assert_eq!(speed_after_holding(0.0).0, 0.0);
+}
+
+#[test]
+fn the_same_button_on_two_pads_is_held_independently() {
+    let mut map = InputMap::empty();
+    map.bind("Pad:a", InputAction::Jump);
+    let mut state = GameState::builder().with_input_map(map).build();
+
+    state.pad_input(0, "Pad:a", true);
+    state.pad_input(1, "Pad:a", true);
+    state.pad_input(0, "Pad:a", false);
+    state.step();
+    assert!(jump_held(&state));
+    state.pad_input(1, "Pad:a", false);
+    state.step();
+    assert!(!jump_held(&state));
+}
+
+#[test]
+fn removing_a_pad_releases_only_its_own_input() {
+    let mut pads = PadInputs::default();
+    pads.button(0, "Pad:a", true);
+    pads.button(0, "Pad:b", true);
+    pads.button(0, "Pad:b", false);
+    pads.button(1, "Pad:x", true);
+    assert_eq!(pads.set_move_x(0, 0.3), 0.3);
+    assert_eq!(pads.set_move_x(1, -0.8), -0.8);
+
+    // Pad 1 is still connected, so its stick keeps driving MoveX
+    assert_eq!(pads.remove(0), (vec!["Pad:a".to_string()], -0.8));
+    assert_eq!(pads.remove(1), (vec!["Pad:x".to_string()], 0.0));
+    assert_eq!(pads.remove(1), (Vec::new(), 0.0));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use rust_2d_engine::components::{Collider, Gravity, Health, Layers, Position};
+use rust_2d_engine::prefab::{ComponentDef, PrefabError};
+use rust_2d_engine::{spawn_prefab, GameState, PrefabRegistry};
+use common::state_with_prefabs;
+use specs::WorldExt;
+
+const PREFABS: &str = r#"{
+    "ghost": (parent: "enemy", components: [
+        Collider(shape: Aabb(width: 40.0, height: 40.0), blocks: []),
+    ]),
+    "phantom": (parent: "enemy", components: [
+        Velocity(x: 0.0, y: 0.0),
+        Collider(shape: Aabb(width: 40.0, height: 40.0), layers: ["layer6"], triggers: []),
+    ]),
+    "shy_player": (parent: "player", components: [
+        Collider(shape: Aabb(width: 40.0, height: 40.0), triggers: ["collectible", "goal"]),
+    ]),
+}"#;
+
+fn y_of(state: &GameState, entity: specs::Entity) -> f32 {
+    state.world.read_storage::<Position>().get(entity).unwrap().y
+}
+
+#[test]
+fn layers_are_written_by_name() {
+    let prefabs = PrefabRegistry::parse_ron(r#"{ "a": (components: [
+        Collider(shape: Circle(radius: 5.0), layers: ["enemy", "layer7"], blocks: ["all"]),
+    ]) }"#).unwrap();
+    let expected = Collider::circle(5.0)
+        .with_layers(Layers::ENEMY | Layers(1 << 7))
+        .with_blocks(Layers::ALL);
+    match prefabs.resolve("a").unwrap()[0] {
+        ComponentDef::Collider { shape, offset, layers, blocks, triggers } => {
+            assert_eq!(Collider { shape, offset, layers, blocks, triggers }, expected);
+        },
+        ref other => panic!("unexpected component: {:?}", other),
+    }
+
+    let unknown = PrefabRegistry::parse_ron(r#"{ "a": (components: [ Collider(shape: Circle(radius: 5.0), layers: ["lava"]) ]) }"#);
+    assert!(matches!(unknown, Err(PrefabError::Parse(message)) if message.contains("lava")));
+}
+
+#[test]
+fn particles_land_on_platforms() {
+    let mut state = GameState::new();
+    state.load_level("\n\n\n##########").unwrap();
+    let particle = spawn_prefab(&mut state.world, "particle", (100.0, 20.0)).unwrap();
+    state.world.write_storage::<Gravity>().insert(particle, Gravity::default()).unwrap();
+
+    for _ in 0..40 {
+        state.step();
+    }
+    assert_eq!(y_of(&state, particle), 120.0 - 4.0);
+}
+
+#[test]
+fn bodies_fall_through_layers_they_do_not_block_on() {
+    let mut state = state_with_prefabs(PREFABS);
+    state.load_level("\n##########").unwrap();
+    let enemy = spawn_prefab(&mut state.world, "enemy", (40.0, 0.0)).unwrap();
+    let ghost = spawn_prefab(&mut state.world, "ghost", (200.0, 0.0)).unwrap();
+
+    for _ in 0..30 {
+        state.step();
+    }
+    assert_eq!(y_of(&state, enemy), 0.0);
+    assert!(y_of(&state, ghost) > 40.0, "ghost should fall through the floor");
+}
+
+#[test]
+fn pairs_neither_side_triggers_on_are_ignored() {
+    let mut state = state_with_prefabs(PREFABS);
+    state.load_level("\n##########").unwrap();
+    let player = spawn_prefab(&mut state.world, "shy_player", (100.0, 0.0)).unwrap();
+    spawn_prefab(&mut state.world, "phantom", (110.0, 0.0)).unwrap();
+    let health = |state: &GameState| state.world.read_storage::<Health>().get(player).unwrap().current;
+
+    for _ in 0..10 {
+        state.step();
+    }
+    assert_eq!(health(&state), 100);
+
+    // The default enemy triggers on players
+    spawn_prefab(&mut state.world, "enemy", (120.0, 0.0)).unwrap();
+    state.step();
+    assert_eq!(health(&state), 80);
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::PathBuf;
+
+use rust_2d_engine::components::{Collectible, Enemy, Gravity, Platform, Position, Renderable, Velocity};
+use rust_2d_engine::utils::legend::{Legend, LegendEntry};
+use rust_2d_engine::utils::level_loader::LevelError;
+use rust_2d_engine::GameState;
+use specs::{Join, WorldExt};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust_2d_engine_legend_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn legend_files_skip_comments_and_blank_lines() {
+    let legend = Legend::parse("\
+// A comment, then a blank line
+
+^ = enemy height=20 offset=0,20
+   // An indented comment
+# = decor color=1,2,3
+").unwrap();
+
+    let spikes = legend.get('^').unwrap();
+    assert_eq!(spikes.prefab, "enemy");
+    assert_eq!(spikes.overrides.height, Some(20.0));
+    assert_eq!(spikes.overrides.offset, Some((0.0, 20.0)));
+    // `#` is a tile character, not a comment
+    assert_eq!(legend.get('#').unwrap().prefab, "decor");
+    assert!(legend.get('/').is_none());
+
+    assert!(Legend::parse(&fs::read_to_string("assets/legend.txt").unwrap()).is_ok());
+}
+
+#[test]
+fn overrides_are_parsed_into_their_fields() {
+    let entry = LegendEntry::parse("player width=30 height=50 color=1,2,3 velocity=-5,2.5 gravity=0.5 speed=120 jump_force=400 health=3").unwrap();
+    assert_eq!(entry.prefab, "player");
+    let overrides = entry.overrides;
+    assert_eq!((overrides.width, overrides.height), (Some(30.0), Some(50.0)));
+    assert_eq!(overrides.color, Some((1, 2, 3)));
+    assert_eq!(overrides.velocity, Some((-5.0, 2.5)));
+    assert_eq!(overrides.gravity, Some(0.5));
+    assert_eq!((overrides.speed, overrides.jump_force, overrides.health), (Some(120.0), Some(400.0), Some(3)));
+    assert_eq!(overrides.offset, None);
+}
+
+#[test]
+fn malformed_overrides_are_rejected() {
+    let error = |text: &str| LegendEntry::parse(text).unwrap_err();
+    assert_eq!(error(""), "missing prefab name");
+    assert_eq!(error("coin shiny"), "expected 'key=value', found 'shiny'");
+    assert_eq!(error("coin sparkle=3"), "unknown override 'sparkle'");
+    assert_eq!(error("coin width=wide"), "invalid value 'wide' for 'width'");
+    assert_eq!(error("coin color=1,2"), "invalid value '1,2' for 'color'");
+    assert_eq!(error("coin offset=3"), "invalid value '3' for 'offset'");
+
+    assert_eq!(Legend::parse_entry("ab = coin").unwrap_err(), "legend key 'ab' must be a single character");
+    assert_eq!(Legend::parse("x = coin\n\ny = coin width=\n").unwrap_err(), (3, "invalid value '' for 'width'".to_string()));
+}
+
+#[test]
+fn inline_tiles_spawn_with_their_overrides() {
+    let mut state = GameState::new();
+    state.load_level("\
+name: Inline
+tile: ^ = enemy height=20 offset=0,20 velocity=0,0 gravity=false
+---
+ ^
+###").unwrap();
+
+    let positions = state.world.read_storage::<Position>();
+    let renderables = state.world.read_storage::<Renderable>();
+    let velocities = state.world.read_storage::<Velocity>();
+    let enemies = state.world.read_storage::<Enemy>();
+    let gravity = state.world.read_storage::<Gravity>();
+    let entities = state.world.entities();
+    let (entity, pos, render, vel, _) = (&entities, &positions, &renderables, &velocities, &enemies).join().next().unwrap();
+    assert_eq!((pos.x, pos.y), (40.0, 20.0));
+    assert_eq!((render.width, render.height), (40.0, 20.0));
+    assert_eq!((vel.x, vel.y), (0.0, 0.0));
+    assert!(gravity.get(entity).is_none());
+    assert_eq!((&state.world.read_storage::<Platform>()).join().count(), 3);
+}
+
+#[test]
+fn levels_can_redefine_built_in_characters() {
+    let mut state = GameState::new();
+    state.load_level("tile: C = enemy\n---\nCC\n##").unwrap();
+    assert_eq!((&state.world.read_storage::<Enemy>()).join().count(), 2);
+    assert_eq!((&state.world.read_storage::<Collectible>()).join().count(), 0);
+}
+
+#[test]
+fn malformed_inline_tiles_report_their_header_line() {
+    let mut state = GameState::new();
+    let error = state.load_level("name: Bad\n\ntile: ^ = enemy height=tall\n---\n^").unwrap_err();
+    match error {
+        LevelError::Parse { file, line, column, message } => {
+            assert_eq!((file, line, column), (None, 3, 7));
+            assert_eq!(message, "invalid value 'tall' for 'height'");
+        },
+        other => panic!("expected a parse error, got {}", other),
+    }
+}
+
+#[test]
+fn shared_legend_files_are_resolved_next_to_the_level() {
+    let dir = temp_dir("shared");
+    fs::write(dir.join("tiles.txt"), "// Shared tiles\n~ = coin color=0,0,255\n").unwrap();
+    fs::write(dir.join("lake.level"), "legend: tiles.txt\ntile: C = enemy\n---\n~C\n##").unwrap();
+
+    let mut state = GameState::new();
+    state.load_level_file(dir.join("lake.level")).unwrap();
+    let renderables = state.world.read_storage::<Renderable>();
+    let coins = state.world.read_storage::<Collectible>();
+    let (render, _) = (&renderables, &coins).join().next().unwrap();
+    assert_eq!(render.color, (0, 0, 255));
+    assert_eq!((&state.world.read_storage::<Enemy>()).join().count(), 1);
+}
+
+#[test]
+fn shared_legend_errors_use_the_legend_file_and_its_lines() {
+    let dir = temp_dir("errors");
+    let legend = dir.join("tiles.txt");
+    fs::write(&legend, "// Shared tiles\n\n~ = coin glow=1\n").unwrap();
+    fs::write(dir.join("lake.level"), "name: Lake\nlegend: tiles.txt\n---\n~").unwrap();
+
+    let mut state = GameState::new();
+    match state.load_level_file(dir.join("lake.level")).unwrap_err() {
+        LevelError::Parse { file, line, message, .. } => {
+            assert_eq!(file, Some(legend.clone()));
+            assert_eq!(line, 3);
+            assert_eq!(message, "unknown override 'glow'");
+        },
+        other => panic!("expected a parse error, got {}", other),
+    }
+
+    fs::write(dir.join("lost.level"), "legend: missing.txt\n---\n#").unwrap();
+    match state.load_level_file(dir.join("lost.level")).unwrap_err() {
+        LevelError::Io { path, .. } => assert_eq!(path, dir.join("missing.txt")),
+        other => panic!("expected an I/O error, got {}", other),
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::PathBuf;
+
+use rust_2d_engine::utils::level_loader::LevelError;
+use rust_2d_engine::GameState;
+
+fn temp_level(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rust_2d_engine_levels_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn parse_error(error: LevelError) -> (Option<PathBuf>, usize, usize, String) {
+    match error {
+        LevelError::Parse { file, line, column, message } => (file, line, column, message),
+        other => panic!("expected a parse error, got {}", other),
+    }
+}
+
+#[test]
+fn header_errors_report_their_line_and_column() {
+    let mut state = GameState::new();
+
+    let error = state.load_level("name: Bad\ntile_size: -4\n---\n#").unwrap_err();
+    let (file, line, column, message) = parse_error(error);
+    assert_eq!((file, line, column), (None, 2, 12));
+    assert_eq!(message, "invalid tile size '-4'");
+
+    let error = state.load_level("name: Bad\n\nbackground: 1, 2\n---\n#").unwrap_err();
+    let (file, line, column, message) = parse_error(error);
+    assert_eq!((file, line, column), (None, 3, 13));
+    assert_eq!(message, "invalid color '1, 2', expected 'r, g, b'");
+
+    let error = state.load_level("flavour: sour\n---\n#").unwrap_err();
+    assert_eq!(error.to_string(), "1:1: unknown header key 'flavour'");
+
+    let error = state.load_level("oops\n---\n#").unwrap_err();
+    assert_eq!(error.to_string(), "1:1: expected 'key: value' in level header");
+
+    // Map positions count the header lines
+    let error = state.load_level("name: Bad\n---\n# #\n #x").unwrap_err();
+    assert_eq!(error.to_string(), "4:3: unknown map character 'x'");
+}
+
+#[test]
+fn level_files_load_with_their_header() {
+    let mut state = GameState::new();
+    let info = state.load_level_file("assets/levels/tutorial.level").unwrap();
+    assert_eq!(info.name, "Tutorial");
+    assert_eq!(info.next.as_deref(), Some("level1"));
+    assert_eq!(info.background, (20, 40, 60));
+    assert_eq!(state.level_info(), info);
+    assert_eq!(state.world_bounds().width(), 20.0 * 40.0);
+}
+
+#[test]
+fn unnamed_level_files_are_named_after_their_stem() {
+    let path = temp_level("cellar.level", "tile_size: 20\n---\n#P #\n####");
+    let mut state = GameState::new();
+    let info = state.load_level_file(&path).unwrap();
+    assert_eq!(info.name, "cellar");
+    assert_eq!(state.level_info().name, "cellar");
+    assert_eq!(state.world_bounds().width(), 80.0);
+}
+
+#[test]
+fn level_file_errors_carry_the_path() {
+    let path = temp_level("broken.level", "name: Broken\n---\n#P #\n#%##");
+    let mut state = GameState::new();
+    let error = state.load_level_file(&path).unwrap_err();
+    assert_eq!(error.to_string(), format!("{}:4:2: unknown map character '%'", path.display()));
+    assert_eq!(parse_error(error).0, Some(path));
+
+    let missing = PathBuf::from("assets/levels/missing.level");
+    match state.load_level_file(&missing).unwrap_err() {
+        LevelError::Io { path, .. } => assert_eq!(path, missing),
+        other => panic!("expected an I/O error, got {}", other),
+    }
+}
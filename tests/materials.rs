@@ -0,0 +1,77 @@
+mod common;
+
+use rust_2d_engine::components::{Gravity, Velocity};
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::GameState;
+use common::player;
+use specs::{Join, WorldExt};
+
+/// Runs right for half a second on a floor of `floor` tiles, then lets go.
+/// Returns the top speed and how far the player slid after letting go.
+fn run_and_stop(floor: &str) -> (f32, f32) {
+    let mut state = GameState::new();
+    let level = format!("tile: ~ = {}\n---\nP                  ?\n~~~~~~~~~~~~~~~~~~~~", floor);
+    state.load_level(&level).unwrap();
+    state.step();
+    state.input(InputAction::MoveRight, true);
+    for _ in 0..30 {
+        state.step();
+    }
+    let (released, top_speed) = (player(&state).pos.x, player(&state).vel.x);
+    state.input(InputAction::MoveRight, false);
+    for _ in 0..60 {
+        state.step();
+    }
+    (top_speed, player(&state).pos.x - released)
+}
+
+#[test]
+fn ice_slides_and_mud_slows() {
+    let (speed, slide) = run_and_stop("platform");
+    let (ice_speed, ice_slide) = run_and_stop("ice");
+    let (mud_speed, mud_slide) = run_and_stop("mud");
+
+    assert_eq!(speed, 200.0);
+    assert_eq!(ice_speed, 200.0);
+    assert!(ice_slide > slide * 5.0, "slid {} on ice, {} on stone", ice_slide, slide);
+    assert_eq!(mud_speed, 100.0);
+    assert!(mud_slide < slide, "slid {} in mud, {} on stone", mud_slide, slide);
+}
+
+#[test]
+fn bounce_pads_throw_bodies_back_up_until_they_settle() {
+    let mut state = GameState::new();
+    state.load_level("tile: ~ = bounce_pad\n---\nP\n\n\n\n\n~").unwrap();
+    let mut bounces = 0;
+    let mut falling = false;
+    for _ in 0..600 {
+        state.step();
+        let vel = player(&state).vel;
+        if falling && vel.y < 0.0 {
+            bounces += 1;
+        }
+        falling = vel.y > 0.0;
+    }
+    assert!(bounces >= 2, "bounced {} times", bounces);
+    // Resting on the pad once the bounces die down
+    assert_eq!(player(&state).pos.y, 200.0 - 40.0);
+    assert_eq!(player(&state).vel.y, 0.0);
+}
+
+#[test]
+fn gravity_scale_changes_fall_speed_and_direction() {
+    let mut state = GameState::new();
+    state.load_level("tile: F = enemy gravity=-0.5 velocity=0,0\ntile: H = enemy gravity=0.5 velocity=0,0\n---\n\n\n\nF H E\n\n\n\n\n\n\n").unwrap();
+    for _ in 0..20 {
+        state.step();
+    }
+    let gravity = state.world.read_storage::<Gravity>();
+    let velocities = state.world.read_storage::<Velocity>();
+    let mut fall_speeds: Vec<(f32, f32)> = (&gravity, &velocities).join().map(|(g, v)| (g.scale, v.y)).collect();
+    fall_speeds.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (floating, half, full) = (fall_speeds[0], fall_speeds[1], fall_speeds[2]);
+    assert_eq!((floating.0, half.0, full.0), (-0.5, 0.5, 1.0));
+    assert!(floating.1 < 0.0, "floaters rise, got {}", floating.1);
+    assert!((half.1 * 2.0 - full.1).abs() < 0.01, "half gravity falls at half speed: {} vs {}", half.1, full.1);
+}
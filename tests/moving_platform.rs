@@ -0,0 +1,103 @@
+mod common;
+
+use rust_2d_engine::components::{MovingPlatform, PathMode, Position};
+use rust_2d_engine::GameState;
+use common::{player, state_with_prefabs, PlayerSnapshot};
+use specs::{Join, WorldExt};
+
+fn load(path: &str) -> GameState {
+    let mut state = GameState::new();
+    // The hint only widens the level so the platform has room to move
+    state.load_level(&format!("tile: M = moving_platform {}\n---\n P         ?\n M", path)).unwrap();
+    state
+}
+
+fn platform_pos(state: &GameState) -> (f32, f32) {
+    let platforms = state.world.read_storage::<MovingPlatform>();
+    let positions = state.world.read_storage::<Position>();
+    let (_, pos) = (&platforms, &positions).join().next().unwrap();
+    (pos.x, pos.y)
+}
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).abs() < 0.01 && (a.1 - b.1).abs() < 0.01
+}
+
+#[test]
+fn platforms_follow_their_path() {
+    // 120px at 60px/s takes two seconds each way
+    let mut state = load("path=120,0 speed=60 wait=0.5");
+    let start = platform_pos(&state);
+    for _ in 0..120 {
+        state.step();
+    }
+    assert!(close(platform_pos(&state), (start.0 + 120.0, start.1)), "{:?}", platform_pos(&state));
+
+    // Waits at the end, then comes back
+    for _ in 0..20 {
+        state.step();
+    }
+    assert!(close(platform_pos(&state), (start.0 + 120.0, start.1)));
+    for _ in 0..130 {
+        state.step();
+    }
+    assert!(close(platform_pos(&state), start), "{:?}", platform_pos(&state));
+
+    // Linear paths go round the waypoints and straight back to the start
+    let mut state = load("path=60,0;60,60 speed=60 wait=0 mode=linear");
+    for _ in 0..120 {
+        state.step();
+    }
+    assert!(close(platform_pos(&state), (start.0 + 60.0, start.1 + 60.0)), "{:?}", platform_pos(&state));
+    for _ in 0..85 {
+        state.step();
+    }
+    assert!(close(platform_pos(&state), start), "{:?}", platform_pos(&state));
+}
+
+#[test]
+fn riders_are_carried_sideways() {
+    let mut state = load("path=120,0 speed=60 wait=0");
+    state.step();
+    let start = player(&state).pos;
+    let platform_start = platform_pos(&state);
+    for _ in 0..60 {
+        state.step();
+        let PlayerSnapshot { pos, grounded, .. } = player(&state);
+        assert!(grounded);
+        assert!(close((pos.x - start.x, pos.y), (platform_pos(&state).0 - platform_start.0, start.y)), "{:?}", pos);
+    }
+}
+
+#[test]
+fn riders_stay_grounded_on_platforms_going_down_and_up() {
+    let mut state = load("path=0,120 speed=90");
+    // Catch up with the platform, which starts moving straight away
+    for _ in 0..30 {
+        state.step();
+    }
+    for step in 0..200 {
+        state.step();
+        let PlayerSnapshot { pos: Position { y, .. }, grounded, .. } = player(&state);
+        assert!(grounded, "lost ground on step {}", step);
+        assert!((y + 40.0 - platform_pos(&state).1).abs() < 0.01, "not flush on step {}", step);
+    }
+}
+
+#[test]
+fn platforms_without_a_path_stay_put() {
+    let mut state = state_with_prefabs(r#"{
+        "stuck_platform": (parent: "moving_platform", components: [ MovingPlatform(path: [], speed: 60.0) ]),
+    }"#);
+    state.load_level("tile: M = stuck_platform\n---\n\n M  ?").unwrap();
+    let start = platform_pos(&state);
+    for _ in 0..60 {
+        state.step();
+    }
+    assert_eq!(platform_pos(&state), start);
+
+    let mut platform = MovingPlatform::new(Vec::new(), 60.0, 0.0, PathMode::PingPong);
+    assert_eq!(platform.waypoint(platform.target), None);
+    platform.advance();
+    assert_eq!(platform.waypoint(platform.target), Some((0.0, 0.0)));
+}
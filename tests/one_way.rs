@@ -0,0 +1,51 @@
+mod common;
+
+use rust_2d_engine::components::Position;
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::{GameState, HeadlessRunner, InputScript};
+use common::player;
+
+const LEVEL: &str = "
+
+
+  ------
+  P
+##########
+";
+
+#[test]
+fn jump_up_through_a_ledge_then_drop_back_down() {
+    let mut state = GameState::new();
+    state.load_level(LEVEL).unwrap();
+    let script = InputScript::new()
+        .press(5, InputAction::Jump)
+        .release(40, InputAction::Jump)
+        .hold(InputAction::MoveDown, 80, 90)
+        .press(82, InputAction::Jump)
+        .release(83, InputAction::Jump);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+
+    runner.run(5);
+    assert_eq!(player(&runner.state).pos.y, 160.0);
+
+    // Passes up through the ledge's underside and lands on its top
+    runner.run(75);
+    assert_eq!(player(&runner.state).pos.y, 120.0 - 40.0);
+
+    // Down + jump falls through instead of jumping
+    runner.run(60);
+    assert_eq!(player(&runner.state).pos.y, 160.0);
+}
+
+#[test]
+fn ledges_do_not_block_from_the_side() {
+    let mut state = GameState::new();
+    state.load_level("\n\nP   -\n#########").unwrap();
+    let script = InputScript::new().hold(InputAction::MoveRight, 5, 120);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+
+    runner.run(120);
+    let Position { x, y } = player(&runner.state).pos;
+    assert!(x > 240.0, "walked past the ledge, got x = {}", x);
+    assert_eq!(y, 80.0);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use rust_2d_engine::resources::PhysicsConfig;
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::utils::level_loader::LevelError;
+use rust_2d_engine::GameState;
+use common::player;
+use specs::WorldExt;
+
+/// Highest point (smallest y) of a held jump in a level with `header`.
+fn jump_peak(header: &str) -> f32 {
+    let mut state = GameState::new();
+    state.load_level(&format!("{}\n---\n\n\n\n\n\n\n\nP\n#", header)).unwrap();
+    state.step();
+    state.input(InputAction::Jump, true);
+    let mut peak = player(&state).pos.y;
+    for _ in 0..120 {
+        state.step();
+        peak = peak.min(player(&state).pos.y);
+    }
+    peak
+}
+
+#[test]
+fn levels_set_their_own_physics() {
+    let mut state = GameState::new();
+    let info = state.load_level("gravity: 0, 500\ndrag: 0.9, 1\nrest_speed: 2\nkill_y: 900\n---\nP\n#").unwrap();
+    let expected = PhysicsConfig { gravity: (0.0, 500.0), ground_drag: 0.9, air_drag: 1.0, rest_speed: 2.0, kill_y: Some(900.0) };
+    assert_eq!(info.physics, expected);
+    assert_eq!(*state.world.read_resource::<PhysicsConfig>(), expected);
+
+    // The next level starts from the defaults again
+    state.load_level("P\n#").unwrap();
+    assert_eq!(*state.world.read_resource::<PhysicsConfig>(), PhysicsConfig::default());
+}
+
+#[test]
+fn low_gravity_jumps_higher() {
+    // 600px/s jumps rise 120px under 1500px/s², 360px under 500px/s²
+    let normal = jump_peak("name: normal");
+    let low = jump_peak("gravity: 0, 500");
+    assert!((normal - (280.0 - 120.0)).abs() < 10.0, "normal jump peaked at {}", normal);
+    assert!((low - (280.0 - 360.0)).abs() < 10.0, "low gravity jump peaked at {}", low);
+}
+
+#[test]
+fn falling_below_the_kill_line_respawns_at_the_start() {
+    let mut state = GameState::new();
+    let info = state.load_level("kill_y: 200\n---\n\n P    ?\n ##\n\n\n\n\n\n\n\n").unwrap();
+    assert_eq!(info.spawn, Some((40.0, 40.0)));
+
+    // Walk off the ledge and fall until past the kill line
+    state.input(InputAction::MoveRight, true);
+    for _ in 0..30 {
+        state.step();
+    }
+    state.input(InputAction::MoveRight, false);
+    let mut respawned = false;
+    for _ in 0..60 {
+        let before = player(&state).pos.y;
+        state.step();
+        let after = player(&state).pos;
+        if after.y < before {
+            respawned = true;
+            assert_eq!((after.x, after.y), (40.0, 40.0));
+            assert!(before <= 200.0 && before > 150.0, "respawned from y = {}", before);
+            break;
+        }
+    }
+    assert!(respawned);
+}
+
+#[test]
+fn bad_physics_settings_are_reported() {
+    let mut state = GameState::new();
+    for (header, message) in [
+        ("gravity: down", "invalid gravity 'down'"),
+        ("drag: 0.5, 2", "invalid drag '0.5, 2'"),
+        ("rest_speed: -1", "invalid rest speed '-1'"),
+    ] {
+        match state.load_level(&format!("{}\n---\nP", header)) {
+            Err(LevelError::Parse { line: 1, message: error, .. }) => assert!(error.starts_with(message), "{}", error),
+            other => panic!("expected a parse error for '{}', got {:?}", header, other),
+        }
+    }
+}
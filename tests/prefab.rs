@@ -0,0 +1,107 @@
+mod common;
+
+use rust_2d_engine::components::{Collider, ColliderShape, Enemy, Gravity, Position, Renderable, Velocity};
+use rust_2d_engine::prefab::{ComponentDef, PrefabError};
+use rust_2d_engine::utils::level_loader::LevelError;
+use rust_2d_engine::{spawn_prefab, GameState, PrefabRegistry};
+use common::state_with_prefabs;
+use specs::{Join, WorldExt};
+
+const PREFABS: &str = r#"{
+    "enemy_walker": (parent: "enemy", components: [
+        Velocity(x: 60.0, y: 0.0),
+    ]),
+    "boss": (parent: "enemy_walker", components: [
+        Renderable(width: 80.0, height: 80.0, color: (120, 0, 0)),
+        Health(max: 500),
+    ]),
+}"#;
+
+#[test]
+fn child_prefabs_inherit_and_override_components() {
+    let mut prefabs = PrefabRegistry::default();
+    prefabs.extend(PrefabRegistry::parse_ron(PREFABS).unwrap());
+
+    let boss = prefabs.resolve("boss").unwrap();
+    assert!(boss.contains(&ComponentDef::Enemy));
+    assert!(boss.contains(&ComponentDef::Gravity { scale: 1.0 }));
+    assert!(boss.contains(&ComponentDef::Velocity { x: 60.0, y: 0.0 }));
+    assert!(boss.contains(&ComponentDef::Health { max: 500 }));
+    let renderables = boss.iter().filter(|c| matches!(c, ComponentDef::Renderable { .. })).count();
+    assert_eq!(renderables, 1);
+}
+
+#[test]
+fn json_and_ron_prefabs_match() {
+    let json = r#"{ "enemy_walker": { "parent": "enemy", "components": [ { "Velocity": { "x": 60.0, "y": 0.0 } } ] } }"#;
+    let from_json = PrefabRegistry::parse_json(json).unwrap();
+    let from_ron = PrefabRegistry::parse_ron(PREFABS).unwrap();
+    assert_eq!(from_json.get("enemy_walker"), from_ron.get("enemy_walker"));
+}
+
+#[test]
+fn inheritance_cycles_and_unknown_parents_are_errors() {
+    let prefabs = PrefabRegistry::parse_ron(r#"{ "a": (parent: "b"), "b": (parent: "a"), "c": (parent: "missing") }"#).unwrap();
+    assert!(matches!(prefabs.resolve("a"), Err(PrefabError::Cycle(_))));
+    assert!(matches!(prefabs.resolve("c"), Err(PrefabError::Unknown(name)) if name == "missing"));
+}
+
+#[test]
+fn spawn_prefab_places_entity_with_components() {
+    let mut state = state_with_prefabs(PREFABS);
+    let walker = spawn_prefab(&mut state.world, "enemy_walker", (200.0, 100.0)).unwrap();
+
+    let positions = state.world.read_storage::<Position>();
+    let velocities = state.world.read_storage::<Velocity>();
+    assert_eq!(positions.get(walker).map(|p| (p.x, p.y)), Some((200.0, 100.0)));
+    assert_eq!(velocities.get(walker).map(|v| v.x), Some(60.0));
+    assert!(state.world.read_storage::<Enemy>().get(walker).is_some());
+    assert!(state.world.read_storage::<Gravity>().get(walker).is_some());
+}
+
+#[test]
+fn levels_spawn_prefabs_named_in_the_legend() {
+    let mut state = state_with_prefabs(PREFABS);
+    state.load_level("tile: B = boss gravity=false\n---\n#B#\n###").unwrap();
+
+    let renderables = state.world.read_storage::<Renderable>();
+    let enemies = state.world.read_storage::<Enemy>();
+    let gravity = state.world.read_storage::<Gravity>();
+    let bosses: Vec<_> = (&renderables, &enemies, !&gravity).join().collect();
+    assert_eq!(bosses.len(), 1);
+    assert_eq!(bosses[0].0.width, 80.0);
+}
+
+#[test]
+fn unknown_prefab_is_reported_at_its_map_position() {
+    let mut state = GameState::new();
+    let error = state.load_level("tile: X = nonexistent\n---\n####\n# X#").unwrap_err();
+    match error {
+        LevelError::Parse { line, column, .. } => assert_eq!((line, column), (4, 3)),
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
+#[test]
+fn prefabs_are_scaled_to_the_level_tile_size() {
+    let mut state = GameState::new();
+    state.load_level("tile_size: 32\ntile: S = platform width=80 offset=0,20\n---\n C\n##S").unwrap();
+    assert_eq!(state.world_bounds().width(), 96.0);
+
+    let positions = state.world.read_storage::<Position>();
+    let renderables = state.world.read_storage::<Renderable>();
+    let colliders = state.world.read_storage::<Collider>();
+    let mut tiles: Vec<(f32, f32, f32, f32)> = (&positions, &renderables).join()
+        .map(|(pos, render)| (pos.x, pos.y, render.width, render.height))
+        .collect();
+    tiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    // Platforms meet edge to edge, and legend sizes and offsets scale too
+    assert_eq!(tiles, vec![
+        (0.0, 32.0, 32.0, 32.0),
+        (32.0, 0.0, 16.0, 16.0),
+        (32.0, 32.0, 32.0, 32.0),
+        (64.0, 48.0, 64.0, 32.0),
+    ]);
+    let coin = (&colliders).join().next().unwrap();
+    assert_eq!(coin.shape, ColliderShape::Circle { radius: 8.0 });
+}
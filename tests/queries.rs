@@ -0,0 +1,199 @@
+use rust_2d_engine::components::{CollisionFilter, Enemy, Layers, Position, Velocity};
+use rust_2d_engine::spatial::{RayHit, SpatialGrid};
+use rust_2d_engine::geometry::{ray_shape, sweep_aabb, Shape};
+use rust_2d_engine::GameState;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use specs::{Builder, Join, WorldExt};
+
+fn on(layers: Layers) -> CollisionFilter {
+    CollisionFilter { layers, blocks: Layers::NONE, triggers: Layers::NONE }
+}
+
+#[test]
+fn rays_hit_the_nearest_shape_on_their_layers() {
+    let mut world = specs::World::new();
+    let wall = world.create_entity().build();
+    let ghost = world.create_entity().build();
+    let ball = world.create_entity().build();
+
+    let mut grid = SpatialGrid::new(32.0);
+    grid.update_filtered(wall, (200.0, 0.0, 20.0, 100.0), on(Layers::PLATFORM));
+    grid.update_filtered(ghost, (100.0, 0.0, 20.0, 100.0), on(Layers::ENEMY));
+    grid.update_shape(ball, Shape::Circle { x: 60.0, y: 50.0, radius: 10.0 }, on(Layers::COLLECTIBLE));
+
+    // Layers filter what can be hit
+    let hit = grid.raycast((0.0, 20.0), (1.0, 0.0), 500.0, Layers::PLATFORM).unwrap();
+    assert_eq!(hit, RayHit { entity: wall, point: (200.0, 20.0), normal: (-1.0, 0.0), distance: 200.0 });
+    let hit = grid.raycast((0.0, 20.0), (2.0, 0.0), 500.0, Layers::ALL).unwrap();
+    assert_eq!((hit.entity, hit.distance), (ghost, 100.0));
+    assert_eq!(grid.raycast((0.0, 20.0), (1.0, 0.0), 150.0, Layers::PLATFORM), None);
+
+    // Circles are hit exactly, not at their bounding box
+    let hit = grid.raycast((0.0, 50.0), (1.0, 0.0), 500.0, Layers::ALL).unwrap();
+    assert_eq!((hit.entity, hit.point, hit.normal), (ball, (50.0, 50.0), (-1.0, 0.0)));
+    let corner = grid.raycast((40.0, 52.0), (1.0, -1.0), 30.0, Layers::COLLECTIBLE);
+    assert_eq!(corner, None);
+
+    // Rays starting inside a shape pass out of it
+    let hit = grid.raycast((110.0, 20.0), (1.0, 0.0), 500.0, Layers::ALL).unwrap();
+    assert_eq!(hit.entity, wall);
+}
+
+#[test]
+fn segments_sight_lines_and_sweeps() {
+    let mut world = specs::World::new();
+    let floor = world.create_entity().build();
+    let pillar = world.create_entity().build();
+
+    let mut grid = SpatialGrid::new(64.0);
+    grid.update_filtered(floor, (0.0, 200.0, 400.0, 40.0), on(Layers::PLATFORM));
+    grid.update_filtered(pillar, (150.0, 100.0, 20.0, 100.0), on(Layers::PLATFORM));
+
+    let hit = grid.segment_cast((100.0, 150.0), (100.0, 300.0), Layers::PLATFORM).unwrap();
+    assert_eq!((hit.entity, hit.normal, hit.distance), (floor, (0.0, -1.0), 50.0));
+    assert_eq!(grid.segment_cast((100.0, 150.0), (100.0, 190.0), Layers::PLATFORM), None);
+
+    assert!(!grid.line_of_sight((100.0, 150.0), (300.0, 150.0), Layers::PLATFORM));
+    assert!(grid.line_of_sight((100.0, 50.0), (300.0, 50.0), Layers::PLATFORM));
+    assert!(grid.line_of_sight((100.0, 150.0), (300.0, 150.0), Layers::ENEMY));
+
+    // A box thrown right and down lands against the pillar's side
+    let hit = grid.sweep((100.0, 140.0, 20.0, 20.0), (60.0, 30.0), Layers::PLATFORM).unwrap();
+    assert_eq!((hit.entity, hit.normal), (pillar, (-1.0, 0.0)));
+    assert_eq!(hit.position.0, 130.0);
+    assert!((hit.time - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn unbounded_casts_stop_at_the_edge_of_the_occupied_cells() {
+    let mut world = specs::World::new();
+    let wall = world.create_entity().build();
+    let mut grid = SpatialGrid::new(32.0);
+    grid.update_filtered(wall, (200.0, 0.0, 20.0, 100.0), on(Layers::PLATFORM));
+
+    let hit = grid.raycast((0.0, 20.0), (1.0, 0.0), f32::INFINITY, Layers::PLATFORM).unwrap();
+    assert_eq!((hit.entity, hit.distance), (wall, 200.0));
+    assert_eq!(grid.raycast((0.0, 20.0), (-1.0, 0.0), f32::INFINITY, Layers::PLATFORM), None);
+    assert_eq!(grid.raycast((-1e30, 20.0), (1.0, 0.0), f32::INFINITY, Layers::PLATFORM).map(|hit| hit.entity), Some(wall));
+    assert_eq!(grid.raycast((0.0, 20.0), (1.0, 0.0), f32::NAN, Layers::PLATFORM), None);
+    assert_eq!(grid.raycast((f32::NAN, 20.0), (1.0, 0.0), 500.0, Layers::PLATFORM), None);
+    assert_eq!(grid.raycast((0.0, 20.0), (f32::INFINITY, 0.0), 500.0, Layers::PLATFORM), None);
+    assert!(!grid.line_of_sight((0.0, 20.0), (f32::MAX, 20.0), Layers::PLATFORM));
+
+    let hit = grid.sweep((0.0, 0.0, 20.0, 20.0), (1e30, 0.0), Layers::PLATFORM).unwrap();
+    assert_eq!((hit.entity, hit.position), (wall, (180.0, 0.0)));
+    assert_eq!(grid.sweep((0.0, 0.0, 20.0, 20.0), (f32::INFINITY, 0.0), Layers::PLATFORM), None);
+
+    // Overlap queries only visit occupied cells, however large the area
+    assert_eq!(grid.query_aabb((-1e30, -1e30, 2e30, 2e30)), vec![wall]);
+    grid.clear();
+    assert_eq!(grid.raycast((0.0, 20.0), (1.0, 0.0), f32::INFINITY, Layers::PLATFORM), None);
+}
+
+#[test]
+fn tiles_block_the_line_of_sight() {
+    let mut state = GameState::new();
+    state.load_level("\n  #\n#####").unwrap();
+    let grid = state.world.read_resource::<SpatialGrid>();
+    // Level with the middle of the raised tile, then just above it
+    assert!(!grid.line_of_sight((20.0, 60.0), (180.0, 60.0), Layers::PLATFORM));
+    assert!(grid.line_of_sight((20.0, 30.0), (180.0, 30.0), Layers::PLATFORM));
+    assert!(grid.line_of_sight((20.0, 60.0), (180.0, 60.0), Layers::ENEMY));
+}
+
+#[test]
+fn casts_find_the_same_hit_as_checking_every_shape() {
+    let mut world = specs::World::new();
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut grid = SpatialGrid::new(32.0);
+    let mut shapes = Vec::new();
+    for _ in 0..200 {
+        let entity = world.create_entity().build();
+        let (x, y) = (rng.gen_range(-400.0..400.0), rng.gen_range(-400.0..400.0));
+        let shape = if rng.gen_bool(0.5) {
+            Shape::Rect((x, y, rng.gen_range(2.0..60.0), rng.gen_range(2.0..60.0)))
+        } else {
+            Shape::Circle { x, y, radius: rng.gen_range(2.0..30.0) }
+        };
+        grid.update_shape(entity, shape, on(Layers::PLATFORM));
+        shapes.push((entity, shape));
+    }
+
+    for _ in 0..500 {
+        let origin = (rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0));
+        let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        // Normalised the way `raycast` does, so distances match exactly
+        let length = angle.cos().hypot(angle.sin());
+        let direction = (angle.cos() / length, angle.sin() / length);
+        let max_distance = rng.gen_range(0.0..1000.0);
+        let nearest = shapes.iter()
+            .filter_map(|(entity, shape)| ray_shape(origin, direction, *shape).map(|(distance, _)| (distance, *entity)))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by(|a, b| a.partial_cmp(b).unwrap());
+        let hit = grid.raycast(origin, direction, max_distance, Layers::PLATFORM);
+        assert_eq!(hit.map(|hit| (hit.distance, hit.entity)), nearest, "ray from {:?} along {:?}", origin, direction);
+
+        let rect = (origin.0, origin.1, 20.0, 30.0);
+        let delta = (direction.0 * max_distance, direction.1 * max_distance);
+        let first = shapes.iter()
+            .filter_map(|(entity, shape)| sweep_aabb(rect, delta, shape.bounds()).map(|(time, _)| (time, *entity)))
+            .min_by(|a, b| a.partial_cmp(b).unwrap());
+        let hit = grid.sweep(rect, delta, Layers::PLATFORM);
+        assert_eq!(hit.map(|hit| (hit.time, hit.entity)), first, "box at {:?} swept by {:?}", rect, delta);
+    }
+}
+
+#[test]
+fn enemies_turn_at_walls_and_edges() {
+    // Without gravity, ground drag does not slow the walkers down
+    let mut state = GameState::new();
+    state.load_level("tile: W = enemy gravity=false\n---\n\n\n#  W     \n##########").unwrap();
+    let mut xs = Vec::new();
+    for _ in 0..600 {
+        state.step();
+        let enemies = state.world.read_storage::<Enemy>();
+        let positions = state.world.read_storage::<Position>();
+        let velocities = state.world.read_storage::<Velocity>();
+        let (_, pos, vel) = (&enemies, &positions, &velocities).join().next().unwrap();
+        assert!(vel.x != 0.0, "enemy got stuck at x = {}", pos.x);
+        xs.push(pos.x);
+    }
+    // Paces between the wall on the left and the level's right side
+    let (min, max) = xs.iter().fold((f32::MAX, f32::MIN), |(min, max), x| (min.min(*x), max.max(*x)));
+    assert!((40.0..60.0).contains(&min), "closest to the wall: {}", min);
+    assert!(max > 300.0, "furthest right: {}", max);
+
+    // On a ledge it turns before walking off
+    let mut state = GameState::new();
+    state.load_level("tile: W = enemy gravity=false\n---\n\n\n   W    \n  ####  \n\n##########").unwrap();
+    for _ in 0..600 {
+        state.step();
+        let enemies = state.world.read_storage::<Enemy>();
+        let positions = state.world.read_storage::<Position>();
+        let (_, pos) = (&enemies, &positions).join().next().unwrap();
+        assert!(pos.x >= 80.0 && pos.x + 40.0 <= 240.0, "walked past the ledge's edge: x = {}", pos.x);
+    }
+}
+
+
+/// The enemy's horizontal velocity after one step on a floor, with `row`
+/// as the line above the floor. Enemies start off walking right.
+fn enemy_velocity(row: &str) -> f32 {
+    let mut state = GameState::new();
+    state.load_level(&format!("tile: W = enemy gravity=false\n---\n{}\n####################", row)).unwrap();
+    state.step();
+    let enemies = state.world.read_storage::<Enemy>();
+    let velocities = state.world.read_storage::<Velocity>();
+    (&enemies, &velocities).join().next().unwrap().1.x
+}
+
+#[test]
+fn enemies_turn_towards_a_player_they_can_see() {
+    assert!(enemy_velocity("  P   W") < 0.0);
+    // Not through a wall, nor from too far away
+    assert!(enemy_velocity("  P # W") > 0.0);
+    assert!(enemy_velocity("P        W") > 0.0);
+    // A player ahead keeps it walking the same way
+    assert!(enemy_velocity(" W   P") > 0.0);
+}
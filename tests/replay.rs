@@ -0,0 +1,109 @@
+use rust_2d_engine::engine::ScriptedInput;
+use rust_2d_engine::utils::{InputAction, InputEvent};
+use rust_2d_engine::{GameMode, GameState, HeadlessRunner, InputScript, Replay};
+
+const LEVEL: &str = "name: replay_test
+---
+####################
+#                  #
+#   C   C   C      #
+#  ### ### ###     #
+#                  #
+#      E           #
+#    #####         #
+#            C     #
+#   P      #####   #
+####################
+";
+
+fn new_state(seed: u64) -> GameState {
+    let mut state = GameState::builder()
+        .with_seed(seed)
+        .with_mode(GameMode::Playing)
+        .build();
+    state.load_level(LEVEL).unwrap();
+    state
+}
+
+fn record_session() -> (Replay, u64) {
+    let script = InputScript::new()
+        .hold(InputAction::MoveRight, 5, 90)
+        .press(40, InputAction::Jump)
+        .release(45, InputAction::Jump)
+        .hold(InputAction::MoveLeft, 120, 200);
+
+    let mut state = new_state(7);
+    state.start_recording();
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    runner.run(240);
+
+    let replay = runner.state.stop_recording().expect("recording was started");
+    (replay, runner.state.state_hash())
+}
+
+#[test]
+fn replay_reproduces_recorded_session() {
+    let (replay, recorded_hash) = record_session();
+    assert_eq!(replay.ticks, 240);
+    assert_eq!(replay.level.as_deref(), Some("replay_test"));
+    assert_eq!(replay.inputs.len(), 6);
+
+    let mut runner = HeadlessRunner::new(new_state(replay.seed));
+    runner.play_replay(&replay).unwrap();
+    assert_eq!(runner.state.state_hash(), recorded_hash);
+}
+
+#[test]
+fn replay_survives_a_file_round_trip() {
+    let (replay, recorded_hash) = record_session();
+    let path = std::env::temp_dir().join(format!("rust_2d_engine_replay_{}.txt", std::process::id()));
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(loaded, replay);
+    let mut runner = HeadlessRunner::new(new_state(loaded.seed));
+    runner.play_replay(&loaded).unwrap();
+    assert_eq!(runner.state.state_hash(), recorded_hash);
+}
+
+#[test]
+fn malformed_replay_reports_line() {
+    let err = Replay::parse("seed 1\n12 Fly press\n").unwrap_err();
+    assert!(err.starts_with("line 2:"), "{}", err);
+}
+
+#[test]
+fn replays_need_a_positive_rate_matching_the_state() {
+    for rate in ["0", "-30", "NaN", "inf"] {
+        let err = Replay::parse(&format!("seed 1\nrate {}\n", rate)).unwrap_err();
+        assert!(err.starts_with("line 2: invalid rate"), "{}", err);
+    }
+
+    let replay = Replay::parse("seed 1\nrate 30\nticks 10\n").unwrap();
+    let mut runner = HeadlessRunner::new(new_state(1));
+    assert!(runner.play_replay(&replay).is_err());
+    assert_eq!(runner.state.tick(), 0);
+}
+
+#[test]
+fn inputs_held_when_recording_starts_are_replayed() {
+    // Right is pressed during a paused step, so it is already held down when
+    // recording starts but the world has not moved yet
+    let mut state = GameState::builder().with_seed(3).with_mode(GameMode::Playing).build();
+    state.load_level(LEVEL).unwrap();
+    state.set_paused(true);
+    state.input(InputAction::MoveRight, true);
+    state.step();
+    state.set_paused(false);
+    state.start_recording();
+    let script = InputScript::new().release(60, InputAction::MoveRight);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    runner.run(120);
+    let replay = runner.state.stop_recording().unwrap();
+    assert_eq!(replay.inputs[0], ScriptedInput { frame: 0, event: InputEvent::Action(InputAction::MoveRight, true) });
+
+    let mut replayed = HeadlessRunner::new(new_state(replay.seed));
+    replayed.play_replay(&replay).unwrap();
+    assert_eq!(replayed.state.state_hash(), runner.state.state_hash());
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use rust_2d_engine::components::{OnSlope, Player, Position, Renderable};
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::{GameState, HeadlessRunner, InputScript};
+use common::{player, PlayerSnapshot};
+use specs::{Join, WorldExt};
+
+/// A floor at y = 160 with a plateau at y = 120 between a 45° ramp up and a
+/// 45° ramp down.
+const HILL: &str = "
+
+P
+    /####\\
+###############";
+
+#[test]
+fn walking_over_a_hill_stays_grounded() {
+    let mut state = GameState::new();
+    state.load_level(HILL).unwrap();
+    let script = InputScript::new().hold(InputAction::MoveRight, 20, 400);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+
+    runner.run(20);
+    assert_eq!(player(&runner.state).pos.y, 120.0);
+    let mut highest = f32::MAX;
+    while player(&runner.state).pos.x < 440.0 {
+        runner.step();
+        let PlayerSnapshot { pos, grounded, .. } = player(&runner.state);
+        assert!(grounded, "airborne at x = {}", pos.x);
+        highest = highest.min(pos.y);
+        assert!(runner.frame() < 400, "did not get over the hill");
+    }
+    assert_eq!(highest, 80.0, "should have crossed the plateau");
+    assert_eq!(player(&runner.state).pos.y, 120.0);
+}
+
+#[test]
+fn speed_follows_the_incline() {
+    let mut state = GameState::new();
+    state.load_level(HILL).unwrap();
+    let script = InputScript::new().hold(InputAction::MoveRight, 20, 400);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+    runner.run(22);
+    let flat_start = player(&runner.state).pos.x;
+    runner.step();
+    let flat = player(&runner.state).pos.x - flat_start;
+
+    // Find a step taken entirely on the ramp
+    loop {
+        let PlayerSnapshot { pos: before, slope, .. } = player(&runner.state);
+        runner.step();
+        let PlayerSnapshot { pos: after, slope: slope_after, .. } = player(&runner.state);
+        if slope.is_some() && slope_after.is_some() && after.x - before.x > 0.0 {
+            assert_eq!(slope, Some(1.0));
+            let ratio = (after.x - before.x) / flat;
+            assert!((ratio - 1.0 / 2f32.sqrt()).abs() < 1e-3, "horizontal speed ratio {}", ratio);
+            assert!(((before.y - after.y) - (after.x - before.x)).abs() < 1e-3, "should climb as far as it moves");
+            break;
+        }
+        assert!(runner.frame() < 400, "never walked up the ramp");
+    }
+}
+
+#[test]
+fn shallow_slopes_and_half_tiles() {
+    let mut state = GameState::new();
+    state.load_level("\n P  P\n[]  _\n#######").unwrap();
+
+    for _ in 0..60 {
+        state.step();
+    }
+    let players = state.world.read_storage::<Player>();
+    let positions = state.world.read_storage::<Position>();
+    let on_slopes = state.world.read_storage::<OnSlope>();
+    let mut landed: Vec<_> = (&state.world.entities(), &players, &positions).join()
+        .map(|(entity, _, pos)| (pos.x, pos.y, on_slopes.get(entity).map(|slope| slope.gradient)))
+        .collect();
+    landed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Over the upper half of the shallow ramp, resting on its highest point
+    assert_eq!(landed[0], (40.0, 80.0 - 40.0, Some(0.5)));
+    // Half-height tiles sit on the floor of their cell
+    assert_eq!(landed[1], (160.0, 120.0 - 20.0 - 40.0, None));
+}
+
+#[test]
+fn half_tiles_sit_on_the_cell_floor_at_any_tile_size() {
+    let mut state = GameState::new();
+    state.load_level("tile_size: 32\n---\nP\n_\n#").unwrap();
+    {
+        let positions = state.world.read_storage::<Position>();
+        let renderables = state.world.read_storage::<Renderable>();
+        let half = (&positions, &renderables).join().find(|(_, render)| render.height == 16.0).unwrap();
+        assert_eq!((half.0.y, half.1.width), (32.0 + 16.0, 32.0));
+    }
+    for _ in 0..30 {
+        state.step();
+    }
+    assert_eq!(player(&state).pos.y, 48.0 - 32.0);
+}
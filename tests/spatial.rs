@@ -0,0 +1,57 @@
+use rust_2d_engine::components::{Platform, Position, Renderable};
+use rust_2d_engine::spatial::SpatialGrid;
+use rust_2d_engine::GameState;
+use specs::{Builder, Join, WorldExt};
+
+#[test]
+fn queries_find_touching_entities_across_cells() {
+    let mut world = specs::World::new();
+    let a = world.create_entity().build();
+    let b = world.create_entity().build();
+    let c = world.create_entity().build();
+
+    let mut grid = SpatialGrid::new(32.0);
+    grid.update(a, (0.0, 0.0, 40.0, 40.0));
+    grid.update(b, (100.0, 0.0, 200.0, 20.0));
+    grid.update(c, (40.0, 0.0, 10.0, 10.0));
+
+    // Edges touching count, and entities spanning many cells appear once
+    assert_eq!(grid.query_aabb((20.0, 0.0, 100.0, 5.0)), vec![a, b, c]);
+    assert_eq!(grid.query_aabb((250.0, 10.0, 1.0, 1.0)), vec![b]);
+    assert_eq!(grid.query_point(45.0, 5.0), vec![c]);
+    assert_eq!(grid.query_point(40.0, 20.0), vec![]);
+
+    grid.update(b, (-500.0, -500.0, 10.0, 10.0));
+    assert_eq!(grid.query_aabb((250.0, 10.0, 1.0, 1.0)), vec![]);
+    assert_eq!(grid.query_point(-495.0, -495.0), vec![b]);
+    grid.remove(a);
+    assert_eq!(grid.len(), 2);
+    assert_eq!(grid.query_point(10.0, 10.0), vec![]);
+}
+
+#[test]
+fn grid_follows_the_world() {
+    let mut state = GameState::new();
+    state.load_level("P\n###").unwrap();
+    assert_eq!(state.world.read_resource::<SpatialGrid>().len(), 4);
+
+    let platform = {
+        let platforms = state.world.read_storage::<Platform>();
+        (&state.world.entities(), &platforms).join().next().unwrap().0
+    };
+    state.world.delete_entity(platform).unwrap();
+    let extra = state.world.create_entity()
+        .with(Position { x: 400.0, y: 400.0 })
+        .with(Renderable { width: 40.0, height: 40.0, color: (0, 0, 0) })
+        .with(Platform)
+        .build();
+    state.step();
+
+    let grid = state.world.read_resource::<SpatialGrid>();
+    assert_eq!(grid.rect(platform), None);
+    assert_eq!(grid.query_point(410.0, 410.0), vec![extra]);
+    drop(grid);
+
+    state.load_level("P").unwrap();
+    assert_eq!(state.world.read_resource::<SpatialGrid>().len(), 1);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use rust_2d_engine::components::{CharacterController, Platform, Player, Position, Renderable, Velocity};
+use rust_2d_engine::geometry::{check_aabb, sweep_aabb};
+use rust_2d_engine::utils::InputAction;
+use rust_2d_engine::{GameState, HeadlessRunner, InputScript};
+use common::{player, PlayerSnapshot};
+use specs::{Join, WorldExt};
+
+/// A thin floor (4px) with plenty of room to fall onto it.
+const THIN_FLOOR: &str = "
+tile: - = platform height=4
+---
+
+   P
+
+
+
+
+
+----------
+";
+
+/// Places the player and launches it, with no cap on its fall speed.
+fn set_player(state: &mut GameState, pos: (f32, f32), vel: (f32, f32)) {
+    let players = state.world.read_storage::<Player>();
+    let mut positions = state.world.write_storage::<Position>();
+    let mut velocities = state.world.write_storage::<Velocity>();
+    let mut controllers = state.world.write_storage::<CharacterController>();
+    for (_, p, v, controller) in (&players, &mut positions, &mut velocities, &mut controllers).join() {
+        *p = Position { x: pos.0, y: pos.1 };
+        *v = Velocity { x: vel.0, y: vel.1 };
+        controller.tuning.max_fall_speed = f32::INFINITY;
+    }
+}
+
+fn overlaps_platform(state: &GameState) -> bool {
+    let players = state.world.read_storage::<Player>();
+    let platforms = state.world.read_storage::<Platform>();
+    let positions = state.world.read_storage::<Position>();
+    let renderables = state.world.read_storage::<Renderable>();
+    (&players, &positions, &renderables).join().any(|(_, pos, render)| {
+        let body = (pos.x, pos.y, render.width, render.height);
+        (&platforms, &positions, &renderables).join()
+            .any(|(_, p, r)| check_aabb(body, (p.x, p.y, r.width, r.height)))
+    })
+}
+
+#[test]
+fn fast_fall_lands_on_a_thin_platform() {
+    let mut state = GameState::new();
+    state.load_level(THIN_FLOOR).unwrap();
+    // 6000 px/s is 100px a step at 60 Hz, far more than the 4px floor
+    set_player(&mut state, (120.0, 40.0), (0.0, 6000.0));
+
+    for _ in 0..10 {
+        state.step();
+    }
+    let PlayerSnapshot { pos, vel, grounded, .. } = player(&state);
+    assert_eq!(pos.y, 280.0 - 40.0, "player should rest on top of the floor");
+    assert_eq!(vel.y, 0.0);
+    assert!(grounded);
+}
+
+#[test]
+fn large_timesteps_do_not_tunnel() {
+    // 5 Hz: a single step covers more than the whole level height
+    let mut state = GameState::builder().with_fixed_rate(5.0).build();
+    state.load_level(THIN_FLOOR).unwrap();
+    set_player(&mut state, (120.0, 40.0), (0.0, 2000.0));
+
+    state.step();
+    let PlayerSnapshot { pos, grounded, .. } = player(&state);
+    assert_eq!(pos.y, 240.0);
+    assert!(grounded);
+}
+
+#[test]
+fn fast_diagonal_into_a_wall_stops_at_its_side() {
+    let mut state = GameState::new();
+    state.load_level("
+#
+#
+#    #
+#P   #
+#    #
+######
+").unwrap();
+    // Heading right and down, hitting the wall's face well below its top
+    set_player(&mut state, (130.0, 90.0), (3000.0, 600.0));
+
+    state.step();
+    let PlayerSnapshot { pos, vel, .. } = player(&state);
+    assert_eq!(pos.x, 200.0 - 40.0, "player should stop flush against the wall");
+    assert!(pos.y > 90.0, "player should keep falling along the wall");
+    assert_eq!(vel.x, 0.0);
+    assert!(!overlaps_platform(&state));
+}
+
+#[test]
+fn corner_hits_resolve_on_the_axis_reached_last() {
+    let block = (200.0, 200.0, 40.0, 40.0);
+
+    // Crosses the block's left edge early but its top late: lands on top
+    let (t, normal) = sweep_aabb((150.0, 150.0, 40.0, 40.0), (40.0, 15.0), block).unwrap();
+    assert_eq!(normal, (0.0, -1.0));
+    assert!((t - 10.0 / 15.0).abs() < 1e-5);
+
+    // Crosses the top early but the side late: hits the side
+    let (t, normal) = sweep_aabb((150.0, 150.0, 40.0, 40.0), (15.0, 40.0), block).unwrap();
+    assert_eq!(normal, (-1.0, 0.0));
+    assert!((t - 10.0 / 15.0).abs() < 1e-5);
+
+    // Moving away from a corner it touches is not a hit
+    assert_eq!(sweep_aabb((0.0, 0.0, 10.0, 10.0), (10.0, 10.0), (10.0, -10.0, 10.0, 10.0)), None);
+}
+
+#[test]
+fn walking_across_tile_seams_keeps_the_player_grounded() {
+    let mut state = GameState::new();
+    state.load_level(" P\n##########").unwrap();
+    let script = InputScript::new().hold(InputAction::MoveRight, 10, 50);
+    let mut runner = HeadlessRunner::new(state).with_script(script);
+
+    runner.run(10);
+    let start = player(&runner.state).pos;
+    assert_eq!(start.y, 0.0);
+    for _ in 0..40 {
+        runner.step();
+        let PlayerSnapshot { pos, grounded, .. } = player(&runner.state);
+        assert_eq!(pos.y, start.y);
+        assert!(grounded, "lost ground at x = {}", pos.x);
+    }
+    assert!(player(&runner.state).pos.x > start.x + 100.0);
+}
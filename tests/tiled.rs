@@ -0,0 +1,149 @@
+use rust_2d_engine::components::{DrawOrder, Enemy, Platform, Player, Position, Renderable, Velocity};
+use rust_2d_engine::utils::tiled::{load_tiled_json, load_tiled_tmx, TiledError};
+use rust_2d_engine::GameState;
+use specs::{Join, WorldExt};
+
+const JSON_MAP: &str = r##"{
+    "width": 4, "height": 3, "tilewidth": 32, "tileheight": 32,
+    "orientation": "orthogonal", "infinite": false,
+    "properties": [
+        { "name": "name", "type": "string", "value": "From Tiled" },
+        { "name": "next", "type": "string", "value": "level1" }
+    ],
+    "layers": [
+        { "type": "tilelayer", "name": "background", "width": 4, "height": 3,
+          "properties": [ { "name": "prefab", "type": "string", "value": "decor" } ],
+          "data": [1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0] },
+        { "type": "tilelayer", "name": "ground", "width": 4, "height": 3,
+          "data": [0, 0, 0, 0, 0, 0, 0, 0, 2, 2, 2, 2] },
+        { "type": "group", "name": "actors", "layers": [
+            { "type": "objectgroup", "name": "spawns", "objects": [
+                { "id": 1, "name": "hero", "type": "player", "x": 32, "y": 32, "width": 0, "height": 0, "rotation": 0 },
+                { "id": 2, "name": "", "class": "enemy", "x": 64, "y": 32, "width": 0, "height": 0, "rotation": 0,
+                  "properties": [ { "name": "velocity", "type": "string", "value": "-40,0" },
+                                  { "name": "color", "type": "color", "value": "#ff102030" } ] }
+            ] }
+        ] }
+    ]
+}"##;
+
+const TMX_MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="40" tileheight="40" infinite="0">
+ <properties>
+  <property name="background" value="10, 20, 30"/>
+ </properties>
+ <tileset firstgid="1" source="tiles.tsx"/>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+0,0,0,
+1,1,1
+</data>
+ </layer>
+ <objectgroup id="2" name="spawns" offsetx="5" offsety="0">
+  <object id="1" type="player" x="0" y="0"/>
+  <object id="2" class="coin" gid="3" x="40" y="40" width="20" height="20"/>
+ </objectgroup>
+</map>"#;
+
+#[test]
+fn json_map_spawns_tiles_and_objects_in_layer_order() {
+    let mut state = GameState::new();
+    let info = load_tiled_json(&mut state.world, JSON_MAP).unwrap();
+    assert_eq!(info.name, "From Tiled");
+    assert_eq!(info.next.as_deref(), Some("level1"));
+    assert_eq!(state.world_bounds().width(), 128.0);
+
+    let platforms = state.world.read_storage::<Platform>();
+    let renderables = state.world.read_storage::<Renderable>();
+    let orders = state.world.read_storage::<DrawOrder>();
+    let ground: Vec<_> = (&platforms, &renderables, &orders).join().collect();
+    assert_eq!(ground.len(), 4);
+    assert!(ground.iter().all(|(_, render, order)| render.width == 32.0 && order.layer == 1));
+
+    let players = state.world.read_storage::<Player>();
+    let positions = state.world.read_storage::<Position>();
+    let (_, pos, order) = (&players, &positions, &orders).join().next().unwrap();
+    assert_eq!((pos.x, pos.y, order.layer), (32.0, 32.0, 2));
+
+    let enemies = state.world.read_storage::<Enemy>();
+    let velocities = state.world.read_storage::<Velocity>();
+    let (_, vel, render) = (&enemies, &velocities, &renderables).join().next().unwrap();
+    assert_eq!(vel.x, -40.0);
+    assert_eq!(render.color, (0x10, 0x20, 0x30));
+}
+
+#[test]
+fn tmx_map_matches_tiled_conventions() {
+    let mut state = GameState::new();
+    let info = load_tiled_tmx(&mut state.world, TMX_MAP).unwrap();
+    assert_eq!(info.background, (10, 20, 30));
+    assert_eq!(info.tile_size, 40.0);
+
+    let platforms = state.world.read_storage::<Platform>();
+    assert_eq!((&platforms).join().count(), 3);
+
+    // Tile objects are anchored at their bottom-left corner; layer offsets apply
+    let renderables = state.world.read_storage::<Renderable>();
+    let positions = state.world.read_storage::<Position>();
+    let coin = (&positions, &renderables).join().find(|(_, render)| render.width == 20.0).unwrap();
+    assert_eq!((coin.0.x, coin.0.y), (45.0, 20.0));
+}
+
+#[test]
+fn unsupported_features_are_errors() {
+    let cases = [
+        JSON_MAP.replace("\"orthogonal\"", "\"isometric\""),
+        JSON_MAP.replace("\"infinite\": false", "\"infinite\": true"),
+        JSON_MAP.replace("\"rotation\": 0,\n                  \"properties\"", "\"rotation\": 45,\n                  \"properties\""),
+        JSON_MAP.replace("\"name\": \"ground\",", "\"name\": \"ground\", \"encoding\": \"base64\", \"compression\": \"zlib\","),
+        JSON_MAP.replace("\"type\": \"group\"", "\"type\": \"imagelayer\""),
+        // Flip flags in the top bits of a tile id
+        JSON_MAP.replace("2, 2, 2, 2]", "2, 2, 2, 2147483650]"),
+        JSON_MAP.replace("1, 1, 1, 1, 0", "1, 1, 1, 1073741825, 0"),
+    ];
+    for map in cases {
+        let mut state = GameState::new();
+        let result = load_tiled_json(&mut state.world, &map);
+        assert!(matches!(result, Err(TiledError::Unsupported(_))), "expected unsupported, got {:?}", result);
+        assert_eq!(state.world.read_storage::<Position>().join().count(), 0);
+    }
+
+    let mut state = GameState::new();
+    let polygon = TMX_MAP.replace(r#"<object id="1" type="player" x="0" y="0"/>"#, r#"<object id="1" x="0" y="0"><polygon points="0,0 10,0 0,10"/></object>"#);
+    assert!(matches!(load_tiled_tmx(&mut state.world, &polygon), Err(TiledError::Unsupported(_))));
+    let flipped = TMX_MAP.replace(r#"gid="3""#, r#"gid="2147483651""#);
+    assert!(matches!(load_tiled_tmx(&mut state.world, &flipped), Err(TiledError::Unsupported(_))));
+}
+
+#[test]
+fn hidden_layers_are_skipped() {
+    let mut state = GameState::new();
+    let hidden = JSON_MAP
+        .replace("\"name\": \"background\",", "\"name\": \"background\", \"visible\": false,")
+        .replace("\"type\": \"group\",", "\"type\": \"group\", \"visible\": false,");
+    load_tiled_json(&mut state.world, &hidden).unwrap();
+    // Only the ground layer is left, and it is now the bottom layer
+    let platforms = state.world.read_storage::<Platform>();
+    let orders = state.world.read_storage::<DrawOrder>();
+    assert_eq!((&platforms, &orders).join().filter(|(_, order)| order.layer == 0).count(), 4);
+    assert_eq!(state.world.read_storage::<Position>().join().count(), 4);
+
+    let mut state = GameState::new();
+    let hidden = TMX_MAP.replace(r#"name="spawns""#, r#"name="spawns" visible="0""#);
+    load_tiled_tmx(&mut state.world, &hidden).unwrap();
+    assert_eq!(state.world.read_storage::<Position>().join().count(), 3);
+    assert_eq!(state.world.read_storage::<Player>().join().count(), 0);
+}
+
+#[test]
+fn unknown_prefabs_and_properties_are_reported() {
+    let mut state = GameState::new();
+    let unknown = JSON_MAP.replace("\"type\": \"player\"", "\"type\": \"dragon\"");
+    let error = load_tiled_json(&mut state.world, &unknown).unwrap_err();
+    assert!(error.to_string().contains("dragon"), "{}", error);
+
+    let bad_property = JSON_MAP.replace("\"name\": \"velocity\"", "\"name\": \"wobble\"");
+    assert!(matches!(load_tiled_json(&mut state.world, &bad_property), Err(TiledError::Invalid(_))));
+
+    assert!(matches!(load_tiled_json(&mut state.world, "{ not json"), Err(TiledError::Parse(_))));
+}
@@ -0,0 +1,70 @@
+use rust_2d_engine::engine::FixedTimestep;
+use rust_2d_engine::GameState;
+
+#[test]
+fn frames_run_one_step_per_elapsed_step_length() {
+    let mut timestep = FixedTimestep::new(50.0);
+    assert_eq!(timestep.step(), 0.02);
+    assert_eq!(timestep.advance(0.01), 0);
+    assert_eq!(timestep.advance(0.015), 1);
+    assert_eq!(timestep.advance(0.045), 2);
+    assert_eq!(timestep.advance(-1.0), 0);
+
+    let mut state = GameState::builder().with_fixed_rate(50.0).build();
+    assert_eq!(state.update(0.1), 5);
+}
+
+#[test]
+fn long_frames_are_capped_and_drop_the_leftover_time() {
+    let mut timestep = FixedTimestep::new(100.0).with_max_steps(3);
+    assert_eq!(timestep.advance(1.0), 3);
+    // The rest of the hitch is dropped rather than run on later frames
+    assert_eq!(timestep.advance(0.0), 0);
+    assert_eq!(timestep.alpha(), 0.0);
+
+    let mut state = GameState::builder().with_max_steps_per_frame(2).build();
+    assert_eq!(state.update(1.0), 2);
+    assert_eq!(state.update(0.0), 0);
+}
+
+#[test]
+fn alpha_is_the_fraction_of_a_step_left_over() {
+    let mut timestep = FixedTimestep::new(10.0);
+    assert_eq!(timestep.alpha(), 0.0);
+    timestep.advance(0.05);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-5);
+    for _ in 0..20 {
+        timestep.advance(0.037);
+        assert!((0.0..1.0).contains(&timestep.alpha()), "alpha {}", timestep.alpha());
+    }
+}
+
+#[test]
+#[should_panic(expected = "fixed rate must be positive and finite")]
+fn zero_rates_are_rejected() {
+    GameState::builder().with_fixed_rate(0.0);
+}
+
+#[test]
+#[should_panic(expected = "fixed rate must be positive and finite")]
+fn negative_rates_are_rejected() {
+    GameState::builder().with_fixed_rate(-60.0);
+}
+
+#[test]
+#[should_panic(expected = "fixed rate must be positive and finite")]
+fn nan_rates_are_rejected() {
+    GameState::builder().with_fixed_rate(f32::NAN);
+}
+
+#[test]
+fn alpha_is_pinned_while_paused() {
+    let mut state = GameState::builder().with_fixed_rate(10.0).build();
+    state.update(0.05);
+    assert!((state.alpha() - 0.5).abs() < 1e-5);
+
+    state.set_paused(true);
+    assert_eq!(state.alpha(), 1.0);
+    state.update(0.02);
+    assert_eq!(state.alpha(), 1.0);
+}